@@ -1,15 +1,28 @@
+use std::collections::HashMap;
 use std::io::ErrorKind;
 
 use aws_config::BehaviorVersion;
 use aws_sdk_secretsmanager::Client as SecretsManagerClient;
+use base64::{engine::general_purpose::STANDARD, Engine};
 use lambda_runtime::{service_fn, Error, LambdaEvent};
-use qstash_rs::{client::QstashClient, errors::QstashError};
-use reqwest::StatusCode;
+use qstash_rs::client::QstashClient;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
+// The Lambda function URL event QStash's delivery arrives as.
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct Request {
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub is_base64_encoded: bool,
+}
+
+#[derive(Deserialize)]
+struct Payload {
     pub message_id: String,
 }
 
@@ -54,30 +67,38 @@ impl App {
     }
 
     async fn func_handler(&self, event: LambdaEvent<Request>) -> Result<Response, Error> {
-        match self
-            .qstash_client
-            .get_message(&event.payload.message_id)
-            .await
-        {
+        let request = event.payload;
+        let body = if request.is_base64_encoded {
+            STANDARD.decode(&request.body)?
+        } else {
+            request.body.into_bytes()
+        };
+
+        // Only accept requests signed by QStash, checked against the raw body before parsing it.
+        let signature = request
+            .headers
+            .get("upstash-signature")
+            .ok_or_else(|| Error::from("Missing Upstash-Signature header"))?;
+        if let Err(e) = self.qstash_client.verify_webhook(signature, &body).await {
+            return Err(Error::from(format!("Invalid signature: {}", e)));
+        }
+
+        let payload: Payload = serde_json::from_slice(&body)?;
+        match self.qstash_client.get_message(&payload.message_id).await {
             Ok(message) => Ok(Response {
                 message: json!({ "message": message }).to_string(),
             }),
-            Err(QstashError::RequestFailed(err)) => {
-                let error_message = match err.status() {
-                    Some(StatusCode::BAD_REQUEST) => "Bad request",
-                    Some(StatusCode::NOT_FOUND) => "Message not found",
-                    Some(StatusCode::INTERNAL_SERVER_ERROR) => "Internal server error",
-                    _ => "Unknown error",
+            Err(e) => {
+                let error_message = match e.http_status() {
+                    400 => "Bad request".to_string(),
+                    404 => "Message not found".to_string(),
+                    status => format!("Error getting message ({}): {}", status, e),
                 };
                 Err(Box::new(std::io::Error::new(
                     ErrorKind::Other,
                     error_message,
                 )))
             }
-            Err(e) => Err(Box::new(std::io::Error::new(
-                ErrorKind::Other,
-                format!("Error getting message: {}", e),
-            ))),
         }
     }
 }
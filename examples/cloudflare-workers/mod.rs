@@ -1,10 +1,16 @@
-use reqwest::StatusCode;
-use qstash_rs::{client::QstashClient, errors::QstashError};
+use qstash_rs::client::QstashClient;
+use qstash_rs::signing_keys::verify_signature;
+use serde::Deserialize;
 use serde_json::json;
 use worker::*;
 
+#[derive(Deserialize)]
+struct Payload {
+    message_id: String,
+}
+
 #[event(fetch)]
-pub async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
+pub async fn main(mut req: Request, env: Env, _ctx: Context) -> Result<Response> {
     let api_key = match env.secret("QSTASH_APIKEY") {
         Ok(secret) => secret.to_string(),
         Err(e) => return Response::error(&format!("Error getting API key: {}", e), 500),
@@ -14,14 +20,31 @@ pub async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
         Err(e) => return Response::error(&format!("Error creating Qstash client: {}", e), 500),
     };
 
-    if !matches!(req.method(), Method::Get) {
+    if !matches!(req.method(), Method::Post) {
       return Response::error("Method Not Allowed", 405);
     }
 
-    let url = &req.url()?;
-    let message_id = match url.query_pairs().find(|(key, _)| key == "message_id") {
-        Some((_, value)) => value.to_string(),
-        None => return Response::error("Query parameter 'message_id' is missing", 400),
+    let (current_key, next_key) = match (
+        env.secret("QSTASH_CURRENT_SIGNING_KEY"),
+        env.secret("QSTASH_NEXT_SIGNING_KEY"),
+    ) {
+        (Ok(current), Ok(next)) => (current.to_string(), next.to_string()),
+        _ => return Response::error("Error getting signing keys", 500),
+    };
+
+    // Only accept requests signed by QStash, checked against the raw body before parsing it.
+    let signature = match req.headers().get("Upstash-Signature")? {
+        Some(signature) => signature,
+        None => return Response::error("Missing Upstash-Signature header", 401),
+    };
+    let body = req.bytes().await?;
+    if let Err(e) = verify_signature(&body, &signature, &current_key, &next_key) {
+        return Response::error(&format!("Invalid signature: {}", e), 401);
+    }
+
+    let message_id = match serde_json::from_slice::<Payload>(&body) {
+        Ok(payload) => payload.message_id,
+        Err(_) => return Response::error("Body field 'message_id' is missing", 400),
     };
 
     match qstash_client.get_message(&message_id).await {
@@ -29,16 +52,6 @@ pub async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
             let json_message = json!({ "message": message });
             Response::from_json(&json_message)
         }
-        Err(e) => match e {
-            QstashError::RequestFailed(err) => match err.status() {
-                Some(StatusCode::BAD_REQUEST) => return Response::error("Bad request", 400),
-                Some(StatusCode::NOT_FOUND) => return Response::error("Message not found", 404),
-                Some(StatusCode::INTERNAL_SERVER_ERROR) => {
-                    return Response::error("Internal server error", 500)
-                }
-                _ => return Response::error(&format!("Error getting message: {}", err), 500),
-            },
-            _ => return Response::error(&format!("Error getting message: {}", e), 500),
-        },
+        Err(e) => Response::error(&format!("Error getting message: {}", e), e.http_status()),
     }
 }
\ No newline at end of file
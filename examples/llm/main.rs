@@ -15,7 +15,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     chat_completion_request.model = "meta-llama/Meta-Llama-3-8B-Instruct".to_string();
     chat_completion_request.messages = vec![Message {
         role: "user".to_string(),
-        content: "What is the capital of Türkiye?".to_string(),
+        content: "What is the capital of Türkiye?".into(),
         name: None,
     }];
 
@@ -34,7 +34,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     chat_completion_request.messages = vec![Message {
         role: "user".to_string(),
         content: "Tell me a funny joke"
-            .to_string(),
+            .into(),
         name: None,
     }];
     chat_completion_request.stream = Some(true);
@@ -31,7 +31,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     );
 
     let publish_message_resp = client
-        .publish_message(&destination, headers, body.clone().into())
+        .publish_message(&destination, headers, body.clone())
         .await?;
     println!("Message published successfully to {}!", destination);
     println!(
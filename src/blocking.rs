@@ -0,0 +1,191 @@
+//! A synchronous facade over [`QstashClient`], for code that doesn't run on an async runtime,
+//! such as CLI tools. Mirrors `reqwest::blocking`.
+//!
+//! Each call blocks the current thread until the request completes. Calling into a
+//! `BlockingQstashClient` from within an async runtime panics, code that already runs on one can
+//! hand its runtime to [`BlockingQstashClient::with_handle`] and call in from
+//! `tokio::task::spawn_blocking` instead.
+
+use reqwest::header::HeaderMap;
+use std::future::Future;
+
+use tokio::runtime::{Builder, Handle, Runtime};
+
+use crate::client::QstashClient;
+use crate::errors::QstashError;
+use crate::message_types::{BatchEntry, Message, MessageResponseResult, PublishBody};
+
+pub struct BlockingQstashClient {
+    inner: QstashClient,
+    runtime: BlockingRuntime,
+}
+
+// The runtime driving the requests: either owned by the client or borrowed from the caller.
+enum BlockingRuntime {
+    Owned(Runtime),
+    Shared(Handle),
+}
+
+impl BlockingRuntime {
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        match self {
+            BlockingRuntime::Owned(runtime) => runtime.block_on(future),
+            BlockingRuntime::Shared(handle) => handle.block_on(future),
+        }
+    }
+}
+
+impl BlockingQstashClient {
+    /// Wraps `client`, driving its requests on a dedicated single-threaded runtime.
+    pub fn new(client: QstashClient) -> Result<Self, QstashError> {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(QstashError::RuntimeBuildFailed)?;
+
+        Ok(BlockingQstashClient {
+            inner: client,
+            runtime: BlockingRuntime::Owned(runtime),
+        })
+    }
+
+    /// Wraps `client`, driving its requests on the existing runtime behind `handle` instead of
+    /// starting a new one. Calls must still come from outside the runtime's async tasks, e.g.
+    /// from `tokio::task::spawn_blocking`.
+    pub fn with_handle(client: QstashClient, handle: Handle) -> Self {
+        BlockingQstashClient {
+            inner: client,
+            runtime: BlockingRuntime::Shared(handle),
+        }
+    }
+
+    /// The wrapped async client.
+    pub fn inner(&self) -> &QstashClient {
+        &self.inner
+    }
+
+    pub fn publish_message(
+        &self,
+        destination: &str,
+        headers: HeaderMap,
+        body: impl Into<PublishBody>,
+    ) -> Result<MessageResponseResult, QstashError> {
+        self.runtime
+            .block_on(self.inner.publish_message(destination, headers, body))
+    }
+
+    pub fn enqueue_message(
+        &self,
+        destination: &str,
+        queue_name: &str,
+        headers: HeaderMap,
+        body: impl Into<PublishBody>,
+    ) -> Result<MessageResponseResult, QstashError> {
+        self.runtime.block_on(
+            self.inner
+                .enqueue_message(destination, queue_name, headers, body),
+        )
+    }
+
+    pub fn batch_messages(
+        &self,
+        batch_entries: Vec<BatchEntry>,
+    ) -> Result<Vec<MessageResponseResult>, QstashError> {
+        self.runtime
+            .block_on(self.inner.batch_messages(batch_entries))
+    }
+
+    pub fn get_message(&self, message_id: &str) -> Result<Message, QstashError> {
+        self.runtime.block_on(self.inner.get_message(message_id))
+    }
+
+    pub fn cancel_message(&self, message_id: &str) -> Result<(), QstashError> {
+        self.runtime.block_on(self.inner.cancel_message(message_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::Method::{GET, POST};
+    use httpmock::MockServer;
+    use reqwest::StatusCode;
+    use reqwest::Url;
+
+    fn blocking_client(server: &MockServer) -> BlockingQstashClient {
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        BlockingQstashClient::new(client).expect("Failed to build BlockingQstashClient")
+    }
+
+    #[test]
+    fn test_blocking_publish_message() {
+        let server = MockServer::start();
+        let publish_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/publish/https://example.com/publish")
+                .header("Authorization", "Bearer test_api_key")
+                .body("Hello");
+            then.status(StatusCode::OK.as_u16())
+                .json_body(json!({"messageId": "msg123"}));
+        });
+        let client = blocking_client(&server);
+
+        let result = client
+            .publish_message("https://example.com/publish", HeaderMap::new(), "Hello")
+            .unwrap();
+
+        publish_mock.assert();
+        assert_eq!(result.into_responses()[0].message_id, "msg123");
+    }
+
+    #[test]
+    fn test_blocking_get_message() {
+        let server = MockServer::start();
+        let get_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/messages/msg123")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .json_body(json!({"messageId": "msg123", "body": "Hello"}));
+        });
+        let client = blocking_client(&server);
+
+        let message = client.get_message("msg123").unwrap();
+
+        get_mock.assert();
+        assert_eq!(message.message_id, "msg123");
+        assert_eq!(message.body, "Hello");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_blocking_with_shared_runtime_handle() {
+        let server = MockServer::start();
+        let get_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/messages/msg123")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .json_body(json!({"messageId": "msg123", "body": "Hello"}));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let client = BlockingQstashClient::with_handle(client, Handle::current());
+
+        let message = tokio::task::spawn_blocking(move || client.get_message("msg123"))
+            .await
+            .unwrap()
+            .unwrap();
+
+        get_mock.assert();
+        assert_eq!(message.message_id, "msg123");
+    }
+}
@@ -1,12 +1,34 @@
-use crate::{errors::QstashError, rate_limited_client::RateLimitedClient};
-use reqwest::Url;
+use crate::{
+    errors::QstashError,
+    rate_limited_client::{RateLimitStatus, RateLimitedClient},
+    signing_keys::SigningKeysCache,
+};
+use std::sync::Arc;
+use std::time::Duration;
 
+use reqwest::{Client, ClientBuilder, Url};
+
+/// The `User-Agent` every request is sent with.
+pub(crate) const USER_AGENT: &str = concat!("qstash-rs/", env!("CARGO_PKG_VERSION"));
+
+/// Cloning is cheap and clones share the connection pool, so a client can be cloned into spawned
+/// tasks or app state instead of being wrapped in an `Arc`.
+#[derive(Clone)]
 pub struct QstashClient {
     pub(crate) client: RateLimitedClient,
     pub(crate) base_url: Url,
+    /// Path prefix put in front of every endpoint path, empty unless set with
+    /// `QstashClientBuilder::base_path`.
+    pub(crate) base_path: String,
+    /// Signing keys fetched by `verify_webhook`, kept for the lifetime of the client and shared
+    /// with its clones.
+    pub(crate) signing_keys: Arc<SigningKeysCache>,
+    /// Whether published messages carry `Upstash-Method: POST` when no method is given.
+    pub(crate) explicit_method_default: bool,
 }
 
 impl QstashClient {
+    #[allow(clippy::should_implement_trait)]
     pub fn default() -> Result<Self, QstashError> {
         let base_url = Url::parse("https://qstash.upstash.io")
             .map_err(|e| QstashError::InvalidBaseUrl(e.to_string()))?;
@@ -14,6 +36,9 @@ impl QstashClient {
         Ok(QstashClient {
             client: RateLimitedClient::new("".to_string()),
             base_url,
+            base_path: String::new(),
+            signing_keys: Arc::new(SigningKeysCache::default()),
+            explicit_method_default: false,
         })
     }
 
@@ -27,12 +52,49 @@ impl QstashClient {
     pub fn builder() -> QstashClientBuilder {
         QstashClientBuilder::default()
     }
+
+    /// The version of this crate, also sent in the `User-Agent` header. Worth including when
+    /// filing a support ticket.
+    pub fn version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
+
+    /// The rate limit status reported by the most recent response that carried rate limit
+    /// headers, or `None` if no such response has been received yet.
+    pub fn last_rate_limit(&self) -> Option<RateLimitStatus> {
+        self.client.last_rate_limit()
+    }
+
+    // Resolves an endpoint path from `endpoints` against the base URL and path.
+    pub(crate) fn endpoint_url(&self, path: &str) -> Result<Url, QstashError> {
+        self.base_url
+            .join(&format!("{}{}", self.base_path, path))
+            .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))
+    }
+
+    /// How many attempts the most recently completed request took, retries included, or `None`
+    /// if no request has completed yet. Shared by all requests made through the client.
+    pub fn last_attempt_count(&self) -> Option<u32> {
+        self.client.last_attempt_count()
+    }
 }
 
 #[derive(Default)]
 pub struct QstashClientBuilder {
     base_url: Option<Url>,
+    base_path: Option<String>,
     api_key: Option<String>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    http2_prior_knowledge: bool,
+    http_client: Option<Client>,
+    max_retries: u32,
+    strict_deserialization: bool,
+    wait_on_rate_limit: bool,
+    max_rate_limit_wait: Option<Duration>,
+    explicit_method_default: bool,
+    #[cfg(feature = "danger-accept-invalid-certs")]
+    danger_accept_invalid_certs: bool,
 }
 
 impl QstashClientBuilder {
@@ -45,23 +107,432 @@ impl QstashClientBuilder {
         Ok(self)
     }
 
+    /// Prefixes every endpoint path with `path`, for gateways that mount QStash under a path
+    /// such as `/qstash-proxy`. The host stays in `base_url`, whose own path is ignored.
+    pub fn base_path(mut self, path: &str) -> Self {
+        self.base_path = Some(path.to_string());
+        self
+    }
+
     pub fn api_key(mut self, api_key: &str) -> Self {
         self.api_key = Some(api_key.to_string());
         self
     }
 
+    /// Sets the total time limit of a request, from connecting until the response body is read.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the time limit for establishing a connection, DNS resolution included.
+    ///
+    /// Keeping this shorter than `timeout` makes unreachable hosts fail fast, which matters on
+    /// serverless cold starts where the whole invocation is billed.
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = Some(connect_timeout);
+        self
+    }
+
+    /// Talks HTTP/2 from the first byte instead of negotiating the protocol, so concurrent
+    /// requests are multiplexed over one connection. Only enable this against servers known to
+    /// speak HTTP/2 in cleartext or over TLS, such as QStash itself: an HTTP/1-only server
+    /// rejects every request. Defaults to `false`.
+    pub fn http2_prior_knowledge(mut self, http2_prior_knowledge: bool) -> Self {
+        self.http2_prior_knowledge = http2_prior_knowledge;
+        self
+    }
+
+    /// Sends requests through `http_client` instead of a client built by the builder, e.g. to
+    /// configure a proxy, custom TLS roots or the connection pool.
+    ///
+    /// The supplied client wins over the builder's HTTP settings: `timeout`, `connect_timeout`,
+    /// `http2_prior_knowledge` and `danger_accept_invalid_certs` are ignored and no `User-Agent`
+    /// is set, configure those on the client itself. The API key is still sent with every
+    /// request.
+    pub fn http_client(mut self, http_client: Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    /// Retries requests that fail to connect or get a 5xx response up to `max_retries` times,
+    /// with exponential backoff starting at 100ms. Defaults to `0`, which never retries.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Fails with `QstashError::StrictResponseParseError` when a response has fields the
+    /// response types don't know about, to catch API changes early. Defaults to `false`, which
+    /// ignores unknown fields so new API fields don't break the client.
+    pub fn strict_deserialization(mut self, strict_deserialization: bool) -> Self {
+        self.strict_deserialization = strict_deserialization;
+        self
+    }
+
+    /// When a request hits the daily or burst rate limit, sleeps until the window resets and
+    /// retries it once instead of returning the rate limit error. Defaults to `false`.
+    pub fn wait_on_rate_limit(mut self, wait_on_rate_limit: bool) -> Self {
+        self.wait_on_rate_limit = wait_on_rate_limit;
+        self
+    }
+
+    /// Caps how long `wait_on_rate_limit` sleeps, so a bogus reset far in the future doesn't
+    /// stall the request. Defaults to 60 seconds.
+    pub fn max_rate_limit_wait(mut self, max_rate_limit_wait: Duration) -> Self {
+        self.max_rate_limit_wait = Some(max_rate_limit_wait);
+        self
+    }
+
+    /// Sends `Upstash-Method: POST` with published and enqueued messages that don't set a method,
+    /// instead of relying on QStash defaulting to POST. Defaults to `false`.
+    pub fn explicit_method_default(mut self, explicit_method_default: bool) -> Self {
+        self.explicit_method_default = explicit_method_default;
+        self
+    }
+
+    /// **DANGER:** disables TLS certificate validation for every request made by the client.
+    ///
+    /// Only meant for testing against a local QStash instance behind a self-signed certificate.
+    /// With this enabled, any certificate is trusted, including expired ones and ones issued for
+    /// other hosts, which makes the connection open to man-in-the-middle attacks.
+    /// Never enable this in production. Defaults to `false`.
+    #[cfg(feature = "danger-accept-invalid-certs")]
+    pub fn danger_accept_invalid_certs(mut self, accept_invalid_certs: bool) -> Self {
+        self.danger_accept_invalid_certs = accept_invalid_certs;
+        self
+    }
+
     pub fn build(self) -> Result<QstashClient, QstashError> {
+        let http_client = match self.http_client {
+            Some(http_client) => http_client,
+            None => self
+                .http_client_builder()
+                .build()
+                .map_err(QstashError::HttpClientBuildFailed)?,
+        };
         let base_url = self.base_url;
         let api_key = self.api_key.unwrap_or_default();
 
         let mut qstash_client = QstashClient::default()?;
-        qstash_client.client = RateLimitedClient::new(api_key);
+        let mut client = RateLimitedClient::with_http_client(api_key, http_client)
+            .with_max_retries(self.max_retries)
+            .with_strict_deserialization(self.strict_deserialization)
+            .with_wait_on_rate_limit(self.wait_on_rate_limit);
+        if let Some(max_rate_limit_wait) = self.max_rate_limit_wait {
+            client = client.with_max_rate_limit_wait(max_rate_limit_wait);
+        }
+        qstash_client.client = client;
+        qstash_client.explicit_method_default = self.explicit_method_default;
 
         if let Some(base_url) = base_url {
             qstash_client.base_url = base_url;
         }
+        if let Some(base_path) = self.base_path {
+            let base_path = base_path.trim_matches('/');
+            if !base_path.is_empty() {
+                qstash_client.base_path = format!("/{}", base_path);
+            }
+        }
 
         Ok(qstash_client)
     }
+
+    fn http_client_builder(&self) -> ClientBuilder {
+        let mut builder = Client::builder().user_agent(USER_AGENT);
+
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        if let Some(connect_timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+
+        if self.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+
+        #[cfg(feature = "danger-accept-invalid-certs")]
+        {
+            builder = builder.danger_accept_invalid_certs(self.danger_accept_invalid_certs);
+        }
+
+        builder
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_uses_defaults() {
+        let client_builder = QstashClient::builder().api_key("test_api_key");
+        let http_client_builder = format!("{:?}", client_builder.http_client_builder());
+        assert!(!http_client_builder.contains("danger_accept_invalid_certs"));
+
+        let client = client_builder
+            .build()
+            .expect("Failed to build QstashClient");
+        assert_eq!(client.base_url.as_str(), "https://qstash.upstash.io/");
+    }
+
+    #[test]
+    fn test_builder_timeouts() {
+        let client_builder = QstashClient::builder()
+            .timeout(Duration::from_secs(30))
+            .connect_timeout(Duration::from_secs(2));
+        let http_client_builder = format!("{:?}", client_builder.http_client_builder());
+        assert!(http_client_builder.contains("connect_timeout: 2s"));
+        assert!(http_client_builder.contains("timeout: 30s"));
+
+        assert!(client_builder.build().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_builder_http2_prior_knowledge() {
+        let client_builder = QstashClient::builder();
+        let http_client_builder = format!("{:?}", client_builder.http_client_builder());
+        assert!(!http_client_builder.contains("http2_prior_knowledge"));
+
+        let server = httpmock::MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.path("/v2/queues/");
+            then.status(200).body("[]");
+        });
+        let client_builder = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .http2_prior_knowledge(true);
+        let http_client_builder = format!("{:?}", client_builder.http_client_builder());
+        assert!(http_client_builder.contains("http2_prior_knowledge: true"));
+
+        let client = client_builder
+            .build()
+            .expect("Failed to build QstashClient");
+        // The mock server only speaks HTTP/1, so the HTTP/2 connection preface is rejected.
+        let result = client.list_queues().await;
+        assert!(matches!(result, Err(QstashError::RequestFailed(_))));
+        mock.assert_hits(0);
+    }
+
+    #[tokio::test]
+    async fn test_version_is_sent_as_user_agent() {
+        assert!(!QstashClient::version().is_empty());
+        let user_agent = format!("qstash-rs/{}", QstashClient::version());
+
+        let server = httpmock::MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.path("/v2/queues/")
+                .header("User-Agent", user_agent.as_str());
+            then.status(200).body("[]");
+        });
+
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        assert!(client.list_queues().await.is_ok());
+
+        let mut client = QstashClient::new("test_api_key".to_string()).unwrap();
+        client.base_url = Url::parse(&server.base_url()).unwrap();
+        assert!(client.list_queues().await.is_ok());
+
+        mock.assert_hits(2);
+    }
+
+    #[cfg(feature = "danger-accept-invalid-certs")]
+    #[test]
+    fn test_builder_danger_accept_invalid_certs() {
+        let client_builder = QstashClient::builder()
+            .api_key("test_api_key")
+            .danger_accept_invalid_certs(true);
+        let http_client_builder = format!("{:?}", client_builder.http_client_builder());
+        assert!(http_client_builder.contains("danger_accept_invalid_certs: true"));
+
+        assert!(client_builder.build().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_cloned_clients_issue_concurrent_requests() {
+        let server = httpmock::MockServer::start();
+        let mocks: Vec<_> = ["msg1", "msg2"]
+            .into_iter()
+            .map(|message_id| {
+                server.mock(|when, then| {
+                    when.path(format!("/v2/messages/{}", message_id))
+                        .header("Authorization", "Bearer test_api_key");
+                    then.status(200)
+                        .json_body(serde_json::json!({"messageId": message_id}));
+                })
+            })
+            .collect();
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let clone = client.clone();
+
+        let (first, second) = tokio::try_join!(
+            tokio::spawn(async move { client.get_message("msg1").await }),
+            tokio::spawn(async move { clone.get_message("msg2").await }),
+        )
+        .unwrap();
+
+        assert_eq!(first.unwrap().message_id, "msg1");
+        assert_eq!(second.unwrap().message_id, "msg2");
+        mocks.iter().for_each(|mock| mock.assert());
+    }
+
+    #[tokio::test]
+    async fn test_cloned_clients_share_connection_pool() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // A keep-alive server counting the connections it accepts.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let accepted = connections.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                accepted.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let body = r#"{"messageId":"msg1"}"#;
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    let mut buffer = [0; 4096];
+                    while let Ok(read) = stream.read(&mut buffer).await {
+                        if read == 0 || stream.write_all(response.as_bytes()).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&format!("http://{}", address)).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let clone = client.clone();
+
+        client.get_message("msg1").await.unwrap();
+        clone.get_message("msg1").await.unwrap();
+
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_builder_uses_supplied_http_client() {
+        let server = httpmock::MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.path("/v2/queues/")
+                .header("X-Custom-Header", "custom-value")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(200).body("[]");
+        });
+        let mut default_headers = reqwest::header::HeaderMap::new();
+        default_headers.insert(
+            "X-Custom-Header",
+            reqwest::header::HeaderValue::from_static("custom-value"),
+        );
+        let http_client = Client::builder()
+            .default_headers(default_headers)
+            .build()
+            .unwrap();
+
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .timeout(Duration::from_secs(30))
+            .http_client(http_client)
+            .build()
+            .expect("Failed to build QstashClient");
+
+        assert!(client.list_queues().await.is_ok());
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_last_rate_limit_tolerates_missing_headers() {
+        let server = httpmock::MockServer::start();
+        let limited_mock = server.mock(|when, then| {
+            when.path("/v2/queues/");
+            then.status(200)
+                .header("Burst-RateLimit-Limit", "100")
+                .header("Burst-RateLimit-Reset", "soon")
+                .body("[]");
+        });
+        let plain_mock = server.mock(|when, then| {
+            when.path("/v2/schedules");
+            then.status(200).body("[]");
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        client.list_queues().await.unwrap();
+        // The remaining quota wasn't sent and the reset isn't a number: both are unknown, not 0.
+        let expected = Some(RateLimitStatus {
+            limit: Some(100),
+            remaining: None,
+            reset: None,
+        });
+        assert_eq!(client.last_rate_limit(), expected);
+
+        // A response without rate limit headers keeps the last known status.
+        client.list_schedules().await.unwrap();
+        assert_eq!(client.last_rate_limit(), expected);
+
+        limited_mock.assert();
+        plain_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_base_path_prefixes_endpoint_paths() {
+        let server = httpmock::MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(httpmock::Method::POST)
+                .path("/qstash-proxy/v2/publish/https://example.com");
+            then.status(200).body(r#"{"messageId": "msg123"}"#);
+        });
+
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .base_path("/qstash-proxy/")
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        assert_eq!(
+            client.endpoint_url("/v2/queues/").unwrap().path(),
+            "/qstash-proxy/v2/queues/"
+        );
+        assert!(client
+            .publish_message(
+                "https://example.com",
+                reqwest::header::HeaderMap::new(),
+                "hello"
+            )
+            .await
+            .is_ok());
+
+        mock.assert();
+    }
+}
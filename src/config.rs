@@ -0,0 +1,423 @@
+use futures::try_join;
+use serde::{Deserialize, Serialize};
+
+use crate::client::QstashClient;
+use crate::errors::QstashError;
+use crate::queues::{Queue, UpsertQueueRequest};
+use crate::schedules::Schedule;
+use crate::url_groups::UrlGroup;
+
+impl QstashClient {
+    /// Snapshots the queues, schedules and URL groups of the account into a single struct
+    /// that can be serialized, e.g. to keep the QStash setup under version control.
+    pub async fn export_config(&self) -> Result<QstashConfig, QstashError> {
+        let (queues, schedules, url_groups) = try_join!(
+            self.list_queues(),
+            self.list_schedules(),
+            self.list_url_groups()
+        )?;
+
+        Ok(QstashConfig {
+            queues,
+            schedules,
+            url_groups,
+        })
+    }
+
+    /// Provisions the queues, URL groups and schedules of `config`, typically one produced by
+    /// `export_config`.
+    ///
+    /// Queues are applied first and schedules last, so schedules can target the queues and URL
+    /// groups they depend on. Schedules are recreated under their exported id, so applying the
+    /// same config twice doesn't duplicate them. Queues and schedules are paused or resumed to
+    /// match `config`. Nothing is removed: items missing from `config` are left untouched.
+    pub async fn apply_config(&self, config: QstashConfig) -> Result<(), QstashError> {
+        let queue_states: Vec<(String, bool)> = config
+            .queues
+            .iter()
+            .map(|queue| (queue.name.clone(), queue.paused))
+            .collect();
+        let upsert_requests = config
+            .queues
+            .into_iter()
+            .map(|queue| UpsertQueueRequest {
+                queue_name: queue.name,
+                parallelism: queue.parallelism,
+            })
+            .collect();
+        self.upsert_queues(upsert_requests).await?;
+        for (queue_name, paused) in queue_states {
+            if paused {
+                self.pause_queue(&queue_name).await?;
+            } else {
+                self.resume_queue(&queue_name).await?;
+            }
+        }
+
+        for url_group in config.url_groups {
+            self.upsert_url_group(url_group).await?;
+        }
+
+        for schedule in config.schedules {
+            let headers = schedule.create_headers()?;
+            let response = self
+                .create_schedule(&schedule.destination, headers, schedule.body.into_bytes())
+                .await?;
+            if schedule.paused {
+                self.pause_schedule(&response.schedule_id).await?;
+            } else {
+                self.resume_schedule(&response.schedule_id).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Queues, schedules and URL groups of an account, as returned by `export_config`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct QstashConfig {
+    pub queues: Vec<Queue>,
+    pub schedules: Vec<Schedule>,
+    pub url_groups: Vec<UrlGroup>,
+}
+
+impl QstashConfig {
+    /// Reports what changed going from `self` to `other`, e.g. from the config kept under version
+    /// control to a fresh `export_config`, to detect changes made in the console.
+    ///
+    /// Queues and URL groups are matched by name, schedules by id. Only the settings `apply_config`
+    /// provisions are compared, so timestamps, queue lag and the next schedule time don't count
+    /// as drift.
+    pub fn diff(&self, other: &QstashConfig) -> ConfigDiff {
+        ConfigDiff {
+            queues: diff_items(
+                &self.queues,
+                &other.queues,
+                |queue| &queue.name,
+                |a, b| a.parallelism == b.parallelism && a.paused == b.paused,
+            ),
+            schedules: diff_items(
+                &self.schedules,
+                &other.schedules,
+                |schedule| &schedule.id,
+                |a, b| {
+                    a.cron == b.cron
+                        && a.destination == b.destination
+                        && a.method == b.method
+                        && a.header == b.header
+                        && a.body == b.body
+                        && a.retries == b.retries
+                        && a.delay == b.delay
+                        && a.callback == b.callback
+                        && a.paused == b.paused
+                },
+            ),
+            url_groups: diff_items(
+                &self.url_groups,
+                &other.url_groups,
+                |url_group| &url_group.name,
+                |a, b| a.endpoints == b.endpoints,
+            ),
+        }
+    }
+}
+
+/// The differences between two configs, as returned by `QstashConfig::diff`.
+#[derive(Debug, Default, PartialEq)]
+pub struct ConfigDiff {
+    pub queues: ItemDiff,
+    pub schedules: ItemDiff,
+    pub url_groups: ItemDiff,
+}
+
+impl ConfigDiff {
+    /// Whether both configs are equivalent.
+    pub fn is_empty(&self) -> bool {
+        self.queues.is_empty() && self.schedules.is_empty() && self.url_groups.is_empty()
+    }
+}
+
+/// The names (or ids, for schedules) of the items that differ between two configs.
+#[derive(Debug, Default, PartialEq)]
+pub struct ItemDiff {
+    /// Items only present in the other config.
+    pub added: Vec<String>,
+    /// Items missing from the other config.
+    pub removed: Vec<String>,
+    /// Items present in both configs with different settings.
+    pub changed: Vec<String>,
+}
+
+impl ItemDiff {
+    /// Whether no item was added, removed or changed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+fn diff_items<T>(
+    ours: &[T],
+    theirs: &[T],
+    key: fn(&T) -> &str,
+    same_settings: fn(&T, &T) -> bool,
+) -> ItemDiff {
+    let mut diff = ItemDiff::default();
+
+    for item in ours {
+        match theirs.iter().find(|other| key(other) == key(item)) {
+            Some(other) if !same_settings(item, other) => diff.changed.push(key(item).to_string()),
+            Some(_) => {}
+            None => diff.removed.push(key(item).to_string()),
+        }
+    }
+    for item in theirs {
+        if !ours.iter().any(|other| key(other) == key(item)) {
+            diff.added.push(key(item).to_string());
+        }
+    }
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::Method::{GET, POST};
+    use httpmock::MockServer;
+    use reqwest::StatusCode;
+    use reqwest::Url;
+
+    #[tokio::test]
+    async fn test_export_config() {
+        let server = MockServer::start();
+        let queues = json!([{
+            "createdAt": 1,
+            "updatedAt": 2,
+            "name": "queue1",
+            "parallelism": 2,
+            "lag": 0,
+            "paused": false
+        }]);
+        let schedules = json!([{
+            "createdAt": 3,
+            "id": "schedule1",
+            "cron": "* * * * *",
+            "destination": "https://example.com/cron",
+            "method": "POST",
+            "header": {"Content-Type": ["application/json"]},
+            "body": "{}",
+            "isPaused": false
+        }]);
+        let url_groups = json!([{
+            "createdAt": 4,
+            "updatedAt": 5,
+            "name": "group1",
+            "endpoints": [{"name": "endpoint1", "url": "https://example.com/1"}]
+        }]);
+        let queues_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/queues/")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .json_body(queues.clone());
+        });
+        let schedules_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/schedules")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .json_body(schedules.clone());
+        });
+        let url_groups_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/topics")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .json_body(url_groups.clone());
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let result = client.export_config().await;
+
+        queues_mock.assert();
+        schedules_mock.assert();
+        url_groups_mock.assert();
+        let config = serde_json::to_value(result.unwrap()).unwrap();
+        assert_eq!(
+            config,
+            json!({
+                "queues": queues,
+                "schedules": schedules,
+                "urlGroups": url_groups
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_config() {
+        let server = MockServer::start();
+        let config: QstashConfig = serde_json::from_value(json!({
+            "queues": [
+                {"createdAt": 1, "updatedAt": 2, "name": "queue1", "parallelism": 2, "lag": 0},
+                {"createdAt": 1, "updatedAt": 2, "name": "queue2", "parallelism": 1, "lag": 0, "paused": true}
+            ],
+            "schedules": [{
+                "id": "schedule1",
+                "cron": "*/5 * * * *",
+                "destination": "https://example.com/cron",
+                "method": "POST",
+                "header": {"Content-Type": ["application/json"]},
+                "body": "{\"key\":\"value\"}",
+                "retries": 3
+            }, {
+                "id": "schedule2",
+                "cron": "0 0 * * *",
+                "destination": "https://example.com/nightly",
+                "isPaused": true
+            }],
+            "urlGroups": [
+                {"name": "group1", "endpoints": [{"name": "endpoint1", "url": "https://example.com/1"}]},
+                {"name": "empty-group", "endpoints": []}
+            ]
+        }))
+        .unwrap();
+        let queue1_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/queues/")
+                .header("Authorization", "Bearer test_api_key")
+                .json_body(json!({"queueName": "queue1", "parallelism": 2}));
+            then.status(StatusCode::OK.as_u16());
+        });
+        let queue2_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/queues/")
+                .header("Authorization", "Bearer test_api_key")
+                .json_body(json!({"queueName": "queue2", "parallelism": 1}));
+            then.status(StatusCode::OK.as_u16());
+        });
+        let pause_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/queues/queue2/pause")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16());
+        });
+        let resume_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/queues/queue1/resume")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16());
+        });
+        let url_group_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/topics/group1/endpoints")
+                .header("Authorization", "Bearer test_api_key")
+                .json_body(json!({
+                    "endpoints": [{"name": "endpoint1", "url": "https://example.com/1"}]
+                }));
+            then.status(StatusCode::OK.as_u16());
+        });
+        let empty_url_group_mock = server.mock(|when, then| {
+            when.method(POST).path("/v2/topics/empty-group/endpoints");
+            then.status(StatusCode::OK.as_u16());
+        });
+        let schedule_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/schedules/https://example.com/cron")
+                .header("Authorization", "Bearer test_api_key")
+                .header("Upstash-Cron", "*/5 * * * *")
+                .header("Upstash-Schedule-Id", "schedule1")
+                .header("Upstash-Method", "POST")
+                .header("Upstash-Retries", "3")
+                .header("Content-Type", "application/json")
+                .body("{\"key\":\"value\"}");
+            then.status(StatusCode::OK.as_u16())
+                .json_body(json!({"scheduleId": "schedule1"}));
+        });
+        let paused_schedule_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/schedules/https://example.com/nightly")
+                .header("Upstash-Schedule-Id", "schedule2");
+            then.status(StatusCode::OK.as_u16())
+                .json_body(json!({"scheduleId": "schedule2"}));
+        });
+        let resume_schedule_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/schedules/schedule1/resume")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16());
+        });
+        let pause_schedule_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/schedules/schedule2/pause")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16());
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let result = client.apply_config(config).await;
+
+        assert!(result.is_ok());
+        queue1_mock.assert();
+        queue2_mock.assert();
+        pause_mock.assert();
+        resume_mock.assert();
+        url_group_mock.assert();
+        empty_url_group_mock.assert_hits(0);
+        schedule_mock.assert();
+        paused_schedule_mock.assert();
+        resume_schedule_mock.assert();
+        pause_schedule_mock.assert();
+    }
+
+    #[test]
+    fn test_config_diff() {
+        let config: QstashConfig = serde_json::from_value(json!({
+            "queues": [
+                {"createdAt": 1, "updatedAt": 2, "name": "queue1", "parallelism": 2, "lag": 0},
+                {"createdAt": 1, "updatedAt": 2, "name": "queue2", "parallelism": 1, "lag": 0}
+            ],
+            "schedules": [{"id": "schedule1", "cron": "* * * * *", "destination": "https://example.com"}],
+            "urlGroups": [{"name": "group1", "endpoints": [{"url": "https://example.com/1"}]}]
+        }))
+        .unwrap();
+        let drifted: QstashConfig = serde_json::from_value(json!({
+            "queues": [
+                {"createdAt": 1, "updatedAt": 3, "name": "queue1", "parallelism": 2, "lag": 7},
+                {"createdAt": 1, "updatedAt": 2, "name": "queue2", "parallelism": 4, "lag": 0},
+                {"createdAt": 4, "updatedAt": 4, "name": "queue3", "parallelism": 1, "lag": 0}
+            ],
+            "schedules": [{"id": "schedule1", "cron": "* * * * *", "destination": "https://example.com", "nextScheduleTime": 5}],
+            "urlGroups": [{"name": "group1", "endpoints": [{"url": "https://example.com/1"}]}]
+        }))
+        .unwrap();
+
+        let diff = config.diff(&drifted);
+
+        assert_eq!(
+            diff,
+            ConfigDiff {
+                queues: ItemDiff {
+                    added: vec!["queue3".to_string()],
+                    removed: vec![],
+                    changed: vec!["queue2".to_string()],
+                },
+                ..Default::default()
+            }
+        );
+        assert!(!diff.is_empty());
+        assert_eq!(drifted.diff(&config).queues.removed, vec!["queue3"]);
+        assert!(config.diff(&config).is_empty());
+    }
+}
@@ -1,9 +1,27 @@
 use std::collections::HashMap;
 
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures::stream::{self, StreamExt};
+use futures::try_join;
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
 
-use crate::{client::QstashClient, errors::QstashError};
+use crate::{
+    client::QstashClient,
+    endpoints,
+    errors::{none_if_not_found, QstashError},
+    message_types::{deserialize_id, MessageResponseResult, PublishBody},
+    messages::PublishOptions,
+    pagination::Page,
+    queues::Queue,
+    schedules::Schedule,
+};
+
+/// Upper bound on the number of pages `dlq_list_all_messages` fetches before giving up.
+const MAX_DLQ_PAGES: usize = 100;
+
+/// Prefix of the headers that configure QStash rather than being sent to the destination.
+const CONTROL_HEADER_PREFIX: &str = "upstash-";
 
 impl QstashClient {
     pub async fn dlq_list_messages(
@@ -12,50 +30,162 @@ impl QstashClient {
     ) -> Result<DLQMessagesList, QstashError> {
         let request = self
             .client
-            .get_request_builder(
-                Method::GET,
-                self.base_url
-                    .join("/v2/dlq/")
-                    .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
-            )
+            .get_request_builder(Method::GET, self.endpoint_url(&endpoints::dlq())?)
             .query(&query_params.to_query_params());
 
-        let response = self
-            .client
-            .send_request(request)
-            .await?
-            .json::<DLQMessagesList>()
-            .await
-            .map_err(QstashError::ResponseBodyParseError)?;
+        let response = self.client.send_json::<DLQMessagesList>(request).await?;
 
         Ok(response)
     }
 
+    /// Follows the cursor through every page of the DLQ and collects all messages.
+    /// Stops after `MAX_DLQ_PAGES` pages to guard against a cursor that never runs out.
+    pub async fn dlq_list_all_messages(
+        &self,
+        mut query_params: DlqQueryParams,
+    ) -> Result<Vec<DLQMessage>, QstashError> {
+        let mut messages = Vec::new();
+
+        for _ in 0..MAX_DLQ_PAGES {
+            let Page { items, cursor } = self.dlq_list_messages(query_params.clone()).await?.into();
+            messages.extend(items);
+
+            match cursor {
+                Some(cursor) => query_params.cursor = Some(cursor),
+                None => break,
+            }
+        }
+
+        Ok(messages)
+    }
+
     pub async fn dlq_get_message(&self, dlq_id: &str) -> Result<DLQMessage, QstashError> {
         let request = self.client.get_request_builder(
             Method::GET,
-            self.base_url
-                .join(&format!("/v2/dlq/{}", dlq_id))
-                .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
+            self.endpoint_url(&endpoints::dlq_message(dlq_id))?,
         );
 
-        let response = self
-            .client
-            .send_request(request)
-            .await?
-            .json::<DLQMessage>()
-            .await
-            .map_err(QstashError::ResponseBodyParseError)?;
+        let response = self.client.send_json::<DLQMessage>(request).await?;
 
         Ok(response)
     }
 
+    /// Fetches a DLQ message together with the queue and schedule it came from, if any, for
+    /// debugging. A queue or schedule that no longer exists is returned as `None`.
+    pub async fn dlq_message_context(&self, dlq_id: &str) -> Result<DlqContext, QstashError> {
+        let message = self.dlq_get_message(dlq_id).await?;
+
+        let queue = async {
+            match &message.queue_name {
+                Some(queue_name) => none_if_not_found(self.get_queue(queue_name).await),
+                None => Ok(None),
+            }
+        };
+        let schedule = async {
+            match &message.schedule_id {
+                Some(schedule_id) => none_if_not_found(self.get_schedule(schedule_id).await),
+                None => Ok(None),
+            }
+        };
+        let (queue, schedule) = try_join!(queue, schedule)?;
+
+        Ok(DlqContext {
+            message,
+            queue,
+            schedule,
+        })
+    }
+
+    /// Resumes many DLQ messages concurrently like `dlq_resume_message`, with at most
+    /// `max_concurrency` in flight.
+    ///
+    /// Results are returned in the order of `dlq_ids`, one per id paired with it, so a failed
+    /// message doesn't fail the others. A `QstashError::DlqCleanupFailed` result means the
+    /// message was delivered again and still carries the new message ids. Unlike
+    /// `upsert_queues`, failures aren't folded into `QstashError::BulkOperationFailed`, since
+    /// that would drop the new message ids of the messages that were resumed.
+    pub async fn dlq_resume_messages_concurrent(
+        &self,
+        dlq_ids: Vec<String>,
+        max_concurrency: usize,
+    ) -> Vec<(String, Result<MessageResponseResult, QstashError>)> {
+        stream::iter(dlq_ids)
+            .map(|dlq_id| async move {
+                let result = self.dlq_resume_message(&dlq_id).await;
+                (dlq_id, result)
+            })
+            .buffered(max_concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// Gives a DLQ message another delivery attempt: publishes it again to the URL whose
+    /// delivery failed, through its original queue and with its original body, headers and
+    /// delivery options, then removes it from the DLQ so it isn't resumed twice. Returns the
+    /// response of the new publish, which carries the new message id.
+    ///
+    /// A DLQ message of a URL group only stands for the endpoint that failed, so the other
+    /// endpoints of the group don't receive it again.
+    ///
+    /// Delivery is at least once: if removing the message from the DLQ fails after it was
+    /// published, `QstashError::DlqCleanupFailed` is returned with the publish response, and
+    /// resuming the message again would deliver it once more.
+    pub async fn dlq_resume_message(
+        &self,
+        dlq_id: &str,
+    ) -> Result<MessageResponseResult, QstashError> {
+        let message = self.dlq_get_message(dlq_id).await?;
+        let body = message.decoded_body()?.unwrap_or_default();
+
+        // Replay every header QStash stored, including the ones the default denylist drops.
+        let mut options = PublishOptions::new().forward_header_denylist(Vec::<String>::new());
+        if let Some(queue_name) = &message.queue_name {
+            options = options.queue(queue_name);
+        }
+        if let Some(method) = &message.method {
+            let method = Method::from_bytes(method.as_bytes())
+                .map_err(|e| QstashError::InvalidHeader(e.to_string()))?;
+            options = options.method(method);
+        }
+        if let Some(max_retries) = message.max_retries.and_then(|r| u32::try_from(r).ok()) {
+            options = options.retries(max_retries);
+        }
+        if let Some(callback) = &message.callback {
+            options = options.callback(callback);
+        }
+        if let Some(failure_callback) = &message.failure_callback {
+            options = options.failure_callback(failure_callback);
+        }
+        for (name, values) in message.header.iter().flatten() {
+            if name.to_ascii_lowercase().starts_with(CONTROL_HEADER_PREFIX) {
+                continue;
+            }
+            for value in values {
+                options = if name.eq_ignore_ascii_case("Content-Type") {
+                    options.control_header(name, value)
+                } else {
+                    options.forward_header(name, value)
+                };
+            }
+        }
+
+        let response = self
+            .publish_message_with_options(&message.url, options, PublishBody::Bytes(body))
+            .await?;
+
+        match self.dlq_delete_message(dlq_id).await {
+            Ok(()) => Ok(response),
+            Err(err) => Err(QstashError::DlqCleanupFailed {
+                response,
+                error: Box::new(err),
+            }),
+        }
+    }
+
     pub async fn dlq_delete_message(&self, dlq_id: &str) -> Result<(), QstashError> {
         let request = self.client.get_request_builder(
             Method::DELETE,
-            self.base_url
-                .join(&format!("/v2/dlq/{}", dlq_id))
-                .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
+            self.endpoint_url(&endpoints::dlq_message(dlq_id))?,
         );
 
         self.client.send_request(request).await?;
@@ -68,29 +198,21 @@ impl QstashClient {
     ) -> Result<DLQDeleteMessagesResponse, QstashError> {
         let request = self
             .client
-            .get_request_builder(
-                Method::DELETE,
-                self.base_url
-                    .join("/v2/queues/")
-                    .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
-            )
+            .get_request_builder(Method::DELETE, self.endpoint_url(&endpoints::dlq())?)
             .json(&json!({
                 "dlqIds": dlq_ids,
             }));
 
         let response = self
             .client
-            .send_request(request)
-            .await?
-            .json::<DLQDeleteMessagesResponse>()
-            .await
-            .map_err(QstashError::ResponseBodyParseError)?;
+            .send_json::<DLQDeleteMessagesResponse>(request)
+            .await?;
 
         Ok(response)
     }
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct DlqQueryParams {
     // By providing a cursor you can paginate through all of the messages in the DLQ
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -211,6 +333,8 @@ impl DlqQueryParams {
 pub struct DLQMessagesList {
     /// A cursor which you can use in subsequent requests to paginate through all events.
     /// If no cursor is returned, you have reached the end of the events.
+    /// Some responses name it `nextCursor`.
+    #[serde(alias = "nextCursor")]
     pub cursor: Option<String>,
 
     /// Array of messages.
@@ -222,7 +346,7 @@ pub struct DLQMessagesList {
 #[serde(default)]
 pub struct DLQMessage {
     /// A unique identifier for this message.
-    #[serde(rename = "messageId")]
+    #[serde(rename = "messageId", deserialize_with = "deserialize_id")]
     pub message_id: String,
 
     /// The URL to which the message should be delivered.
@@ -238,7 +362,7 @@ pub struct DLQMessage {
     pub caller_ip: String,
 
     /// The unique id within the DLQ. Use this to remove the message from the DLQ DELETE /v2/dlq/{dlqId}.
-    #[serde(rename = "dlqId")]
+    #[serde(rename = "dlqId", deserialize_with = "deserialize_id")]
     pub dlq_id: String,
 
     /// The URL Group (topic) name if this message was sent to a URL Group.
@@ -306,6 +430,45 @@ pub struct DLQMessage {
     pub response_body_base64: Option<String>,
 }
 
+impl DLQMessage {
+    /// The raw bytes of the message body, taken from `body` or decoded from `body_base64`,
+    /// whichever QStash filled in. `None` if neither is present.
+    pub fn decoded_body(&self) -> Result<Option<Vec<u8>>, QstashError> {
+        decode_body(&self.body, &self.body_base64)
+    }
+
+    /// The raw bytes of the response body of the last failed delivery attempt, taken from
+    /// `response_body` or decoded from `response_body_base64`. `None` if neither is present.
+    pub fn decoded_response_body(&self) -> Result<Option<Vec<u8>>, QstashError> {
+        decode_body(&self.response_body, &self.response_body_base64)
+    }
+}
+
+// QStash sends a body as text when it is valid UTF-8 and base64 encoded otherwise, leaving the
+// other field empty.
+fn decode_body(
+    body: &Option<String>,
+    body_base64: &Option<String>,
+) -> Result<Option<Vec<u8>>, QstashError> {
+    match (body, body_base64) {
+        (_, Some(encoded)) if !encoded.is_empty() => STANDARD
+            .decode(encoded)
+            .map(Some)
+            .map_err(QstashError::BodyDecodeError),
+        (Some(body), _) => Ok(Some(body.clone().into_bytes())),
+        (None, Some(_)) => Ok(Some(Vec::new())),
+        (None, None) => Ok(None),
+    }
+}
+
+/// A DLQ message with the live queue and schedule it originated from.
+#[derive(Debug)]
+pub struct DlqContext {
+    pub message: DLQMessage,
+    pub queue: Option<Queue>,
+    pub schedule: Option<Schedule>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DLQDeleteMessagesResponse {
     pub deleted: u32,
@@ -319,7 +482,8 @@ mod tests {
         DLQDeleteMessagesResponse, DLQMessage, DLQMessagesList, DlqQueryParams,
     };
     use crate::errors::QstashError;
-    use httpmock::Method::{DELETE, GET};
+    use crate::message_types::MessageResponseResult;
+    use httpmock::Method::{DELETE, GET, POST};
     use httpmock::MockServer;
     use reqwest::StatusCode;
     use reqwest::Url;
@@ -363,7 +527,7 @@ mod tests {
         };
         let list_mock = server.mock(|when, then| {
             when.method(GET)
-                .path("/v2/dlq/")
+                .path("/v2/dlq")
                 .header("Authorization", "Bearer test_api_key");
             then.status(200)
                 .header("Content-Type", "application/json")
@@ -389,7 +553,7 @@ mod tests {
         let server = MockServer::start();
         let list_mock = server.mock(|when, then| {
             when.method(GET)
-                .path("/v2/dlq/")
+                .path("/v2/dlq")
                 .header("Authorization", "Bearer test_api_key");
             then.status(StatusCode::TOO_MANY_REQUESTS.as_u16())
                 .header("RateLimit-Limit", "1000")
@@ -417,7 +581,7 @@ mod tests {
         let server = MockServer::start();
         let list_mock = server.mock(|when, then| {
             when.method(GET)
-                .path("/v2/dlq/")
+                .path("/v2/dlq")
                 .header("Authorization", "Bearer test_api_key");
             then.status(200)
                 .header("Content-Type", "application/json")
@@ -604,7 +768,7 @@ mod tests {
         let expected_response = DLQDeleteMessagesResponse { deleted: 2 };
         let delete_mock = server.mock(|when, then| {
             when.method(DELETE)
-                .path("/v2/queues/")
+                .path("/v2/dlq")
                 .header("Authorization", "Bearer test_api_key")
                 .json_body(json!({
                     "dlqIds": ["dlq123", "dlq124"]
@@ -632,7 +796,7 @@ mod tests {
         let dlq_ids = vec!["dlq123".to_string(), "dlq124".to_string()];
         let delete_mock = server.mock(|when, then| {
             when.method(DELETE)
-                .path("/v2/queues/")
+                .path("/v2/dlq")
                 .header("Authorization", "Bearer test_api_key")
                 .json_body(json!({
                     "dlqIds": ["dlq123", "dlq124"]
@@ -664,7 +828,7 @@ mod tests {
         let dlq_ids = vec!["dlq123".to_string(), "dlq124".to_string()];
         let delete_mock = server.mock(|when, then| {
             when.method(DELETE)
-                .path("/v2/queues/")
+                .path("/v2/dlq")
                 .header("Authorization", "Bearer test_api_key")
                 .json_body(json!({
                     "dlqIds": ["dlq123", "dlq124"]
@@ -686,4 +850,479 @@ mod tests {
             Err(QstashError::ResponseBodyParseError(_))
         ));
     }
+
+    #[tokio::test]
+    async fn test_dlq_list_all_messages_follows_cursor() {
+        let server = MockServer::start();
+        let first_page_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/dlq")
+                .header("Authorization", "Bearer test_api_key")
+                .query_param("queueName", "queue1")
+                .matches(|req| {
+                    req.query_params
+                        .as_ref()
+                        .is_none_or(|params| params.iter().all(|(key, _)| key != "cursor"))
+                });
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .json_body(json!({
+                    "cursor": "page2",
+                    "messages": [
+                        {"messageId": "msg1", "dlqId": "dlq1"},
+                        {"messageId": "msg2", "dlqId": "dlq2"}
+                    ]
+                }));
+        });
+        let second_page_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/dlq")
+                .header("Authorization", "Bearer test_api_key")
+                .query_param("queueName", "queue1")
+                .query_param("cursor", "page2");
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .json_body(json!({
+                    "messages": [
+                        {"messageId": "msg3", "dlqId": "dlq3"}
+                    ]
+                }));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let query_params = DlqQueryParams {
+            queue_name: Some("queue1".to_string()),
+            ..Default::default()
+        };
+        let result = client.dlq_list_all_messages(query_params).await;
+        first_page_mock.assert();
+        second_page_mock.assert();
+        let messages = result.unwrap();
+        let dlq_ids: Vec<&str> = messages.iter().map(|m| m.dlq_id.as_str()).collect();
+        assert_eq!(dlq_ids, vec!["dlq1", "dlq2", "dlq3"]);
+    }
+
+    #[tokio::test]
+    async fn test_dlq_message_context() {
+        let server = MockServer::start();
+        let get_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/dlq/dlq123")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!({
+                    "messageId": "msg123",
+                    "url": "https://example.com/endpoint",
+                    "createdAt": 1625097600000i64,
+                    "callerIP": "127.0.0.1",
+                    "dlqId": "dlq123",
+                    "queueName": "queue1",
+                    "scheduleId": "sched123"
+                }));
+        });
+        let queue_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/queues/queue1")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!({
+                    "createdAt": 1625097600000i64,
+                    "updatedAt": 1625097600000i64,
+                    "name": "queue1",
+                    "parallelism": 1,
+                    "lag": 4
+                }));
+        });
+        let schedule_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/schedules/sched123")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::NOT_FOUND.as_u16())
+                .json_body(json!({"error": "schedule not found"}));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let context = client.dlq_message_context("dlq123").await.unwrap();
+
+        get_mock.assert();
+        queue_mock.assert();
+        schedule_mock.assert();
+        assert_eq!(context.message.dlq_id, "dlq123");
+        assert_eq!(context.queue.unwrap().lag, 4);
+        assert!(context.schedule.is_none());
+    }
+
+    #[test]
+    fn test_dlq_message_decoded_bodies() {
+        let utf8_only = DLQMessage {
+            body: Some("Hello".to_string()),
+            body_base64: Some(String::new()),
+            response_body: Some("Internal Server Error".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(utf8_only.decoded_body().unwrap(), Some(b"Hello".to_vec()));
+        assert_eq!(
+            utf8_only.decoded_response_body().unwrap(),
+            Some(b"Internal Server Error".to_vec())
+        );
+
+        let base64_only = DLQMessage {
+            body: Some(String::new()),
+            body_base64: Some("/wD+".to_string()),
+            response_body_base64: Some("gA==".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            base64_only.decoded_body().unwrap(),
+            Some(vec![0xff, 0x00, 0xfe])
+        );
+        assert_eq!(
+            base64_only.decoded_response_body().unwrap(),
+            Some(vec![0x80])
+        );
+
+        let empty = DLQMessage::default();
+        assert_eq!(empty.decoded_body().unwrap(), None);
+        assert_eq!(empty.decoded_response_body().unwrap(), None);
+
+        let invalid = DLQMessage {
+            body_base64: Some("not base64!".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            invalid.decoded_body(),
+            Err(QstashError::BodyDecodeError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_dlq_resume_messages_concurrent() {
+        let server = MockServer::start();
+        let mut mocks = Vec::new();
+        for i in 1..=3 {
+            mocks.push(server.mock(|when, then| {
+                when.method(GET)
+                    .path(format!("/v2/dlq/dlq{}", i))
+                    .header("Authorization", "Bearer test_api_key");
+                then.status(200).json_body(json!({
+                    "messageId": format!("msg{}", i),
+                    "dlqId": format!("dlq{}", i),
+                    "url": format!("https://example.com/{}", i),
+                    "method": "PUT",
+                    "maxRetries": 2,
+                    "header": {
+                        "Content-Type": ["application/json"],
+                        "X-Tenant": ["acme"],
+                        "Upstash-Message-Id": [format!("msg{}", i)]
+                    },
+                    "body": format!("{{\"order\":{}}}", i)
+                }));
+            }));
+            mocks.push(server.mock(|when, then| {
+                when.method(POST)
+                    .path(format!("/v2/publish/https://example.com/{}", i))
+                    .header("Authorization", "Bearer test_api_key")
+                    .header("Content-Type", "application/json")
+                    .header("Upstash-Method", "PUT")
+                    .header("Upstash-Retries", "2")
+                    .header("Upstash-Forward-X-Tenant", "acme")
+                    .matches(|req| {
+                        req.headers.as_ref().is_none_or(|headers| {
+                            headers.iter().all(|(name, _)| {
+                                !name.eq_ignore_ascii_case("Upstash-Forward-Upstash-Message-Id")
+                            })
+                        })
+                    })
+                    .body(format!("{{\"order\":{}}}", i));
+                then.status(200)
+                    .json_body(json!({"messageId": format!("resumed{}", i)}));
+            }));
+            mocks.push(server.mock(|when, then| {
+                when.method(DELETE)
+                    .path(format!("/v2/dlq/dlq{}", i))
+                    .header("Authorization", "Bearer test_api_key");
+                then.status(200);
+            }));
+        }
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let dlq_ids = vec!["dlq1".to_string(), "dlq2".to_string(), "dlq3".to_string()];
+
+        let responses = client.dlq_resume_messages_concurrent(dlq_ids, 2).await;
+
+        mocks.iter().for_each(|mock| mock.assert());
+        let dlq_ids: Vec<_> = responses
+            .iter()
+            .map(|(dlq_id, _)| dlq_id.as_str())
+            .collect();
+        assert_eq!(dlq_ids, vec!["dlq1", "dlq2", "dlq3"]);
+        let message_ids: Vec<_> = responses
+            .into_iter()
+            .flat_map(|(_, response)| response.unwrap().into_responses())
+            .map(|response| response.message_id)
+            .collect();
+        assert_eq!(message_ids, vec!["resumed1", "resumed2", "resumed3"]);
+    }
+
+    #[tokio::test]
+    async fn test_dlq_resume_messages_concurrent_reports_failures() {
+        let server = MockServer::start();
+        let missing_mock = server.mock(|when, then| {
+            when.method(GET).path("/v2/dlq/missing");
+            then.status(404);
+        });
+        let get_mock = server.mock(|when, then| {
+            when.method(GET).path("/v2/dlq/dlq1");
+            then.status(200).json_body(json!({
+                "messageId": "msg1",
+                "dlqId": "dlq1",
+                "url": "https://example.com",
+                "body": "hello"
+            }));
+        });
+        let publish_mock = server.mock(|when, then| {
+            when.method(POST).path("/v2/publish/https://example.com");
+            then.status(200).json_body(json!({"messageId": "resumed1"}));
+        });
+        let delete_mock = server.mock(|when, then| {
+            when.method(DELETE).path("/v2/dlq/dlq1");
+            then.status(200);
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let results = client
+            .dlq_resume_messages_concurrent(vec!["missing".to_string(), "dlq1".to_string()], 2)
+            .await;
+
+        missing_mock.assert();
+        get_mock.assert();
+        publish_mock.assert();
+        delete_mock.assert();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, "missing");
+        assert!(matches!(results[0].1, Err(QstashError::RequestFailed(_))));
+        assert_eq!(results[1].0, "dlq1");
+        match &results[1].1 {
+            Ok(MessageResponseResult::URLResponse(response)) => {
+                assert_eq!(response.message_id, "resumed1")
+            }
+            other => panic!("Expected a resumed message, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dlq_resume_message_success() {
+        let server = MockServer::start();
+        let get_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/dlq/dlq123")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(200).json_body(json!({
+                "messageId": "msg123",
+                "dlqId": "dlq123",
+                "url": "https://example.com/endpoint",
+                "topicName": "orders",
+                "queueName": "billing",
+                "method": "POST",
+                "body": "hello"
+            }));
+        });
+        let publish_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/enqueue/billing/https://example.com/endpoint")
+                .header("Authorization", "Bearer test_api_key")
+                .body("hello");
+            then.status(200)
+                .json_body(json!([{"messageId": "resumed123", "url": "https://example.com"}]));
+        });
+        let delete_mock = server.mock(|when, then| {
+            when.method(DELETE)
+                .path("/v2/dlq/dlq123")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(200);
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let response = client.dlq_resume_message("dlq123").await.unwrap();
+
+        get_mock.assert();
+        publish_mock.assert();
+        delete_mock.assert();
+        let message_ids: Vec<_> = response
+            .into_responses()
+            .into_iter()
+            .map(|response| response.message_id)
+            .collect();
+        assert_eq!(message_ids, vec!["resumed123"]);
+    }
+
+    #[tokio::test]
+    async fn test_dlq_resume_message_rate_limit_error() {
+        let server = MockServer::start();
+        let get_mock = server.mock(|when, then| {
+            when.method(GET).path("/v2/dlq/dlq123");
+            then.status(200).json_body(json!({
+                "messageId": "msg123",
+                "dlqId": "dlq123",
+                "url": "https://example.com",
+                "body": "hello"
+            }));
+        });
+        let publish_mock = server.mock(|when, then| {
+            when.method(POST).path("/v2/publish/https://example.com");
+            then.status(StatusCode::TOO_MANY_REQUESTS.as_u16())
+                .header("RateLimit-Limit", "1000")
+                .header("RateLimit-Reset", "1625097600000")
+                .body("Rate limit exceeded");
+        });
+        let delete_mock = server.mock(|when, then| {
+            when.method(DELETE).path("/v2/dlq/dlq123");
+            then.status(200);
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let result = client.dlq_resume_message("dlq123").await;
+
+        get_mock.assert();
+        publish_mock.assert();
+        // The message stays in the DLQ when it couldn't be published again.
+        delete_mock.assert_hits(0);
+        assert!(matches!(
+            result,
+            Err(QstashError::DailyRateLimitExceeded {
+                reset: 1625097600000
+            })
+        ));
+    }
+
+    #[test]
+    fn test_dlq_messages_list_next_cursor() {
+        let list: DLQMessagesList = serde_json::from_value(json!({
+            "nextCursor": "next_cursor",
+            "messages": [{"messageId": "msg1", "dlqId": "dlq1"}]
+        }))
+        .unwrap();
+
+        assert_eq!(list.cursor, Some("next_cursor".to_string()));
+        assert_eq!(list.messages.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_dlq_resume_message_keeps_response_when_delete_fails() {
+        let server = MockServer::start();
+        let get_mock = server.mock(|when, then| {
+            when.method(GET).path("/v2/dlq/dlq123");
+            then.status(200).json_body(json!({
+                "messageId": "msg123",
+                "dlqId": "dlq123",
+                "url": "https://example.com",
+                "body": "hello"
+            }));
+        });
+        let publish_mock = server.mock(|when, then| {
+            when.method(POST).path("/v2/publish/https://example.com");
+            then.status(200)
+                .json_body(json!({"messageId": "resumed123"}));
+        });
+        let delete_mock = server.mock(|when, then| {
+            when.method(DELETE).path("/v2/dlq/dlq123");
+            then.status(StatusCode::INTERNAL_SERVER_ERROR.as_u16());
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let result = client.dlq_resume_message("dlq123").await;
+
+        get_mock.assert();
+        publish_mock.assert();
+        delete_mock.assert();
+        match result {
+            Err(QstashError::DlqCleanupFailed { response, error }) => {
+                let message_ids: Vec<_> = response
+                    .into_responses()
+                    .into_iter()
+                    .map(|response| response.message_id)
+                    .collect();
+                assert_eq!(message_ids, vec!["resumed123"]);
+                assert!(matches!(*error, QstashError::RequestFailed(_)));
+            }
+            other => panic!("Expected DlqCleanupFailed, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dlq_resume_message_forwards_authorization() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/v2/dlq/dlq123");
+            then.status(200).json_body(json!({
+                "messageId": "msg123",
+                "dlqId": "dlq123",
+                "url": "https://example.com/endpoint",
+                "header": {
+                    "Authorization": ["Bearer destination_token"],
+                    "X-Trace": ["abc"]
+                },
+                "body": "hello"
+            }));
+        });
+        let publish_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/publish/https://example.com/endpoint")
+                .header("Authorization", "Bearer test_api_key")
+                .header("Upstash-Forward-Authorization", "Bearer destination_token")
+                .header("Upstash-Forward-X-Trace", "abc");
+            then.status(200)
+                .json_body(json!({"messageId": "resumed123", "url": "https://example.com"}));
+        });
+        let delete_mock = server.mock(|when, then| {
+            when.method(DELETE).path("/v2/dlq/dlq123");
+            then.status(200);
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        client.dlq_resume_message("dlq123").await.unwrap();
+
+        publish_mock.assert();
+        delete_mock.assert();
+    }
 }
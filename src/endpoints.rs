@@ -0,0 +1,168 @@
+//! Paths of the QStash REST API, relative to the client's base URL.
+//!
+//! Every request builds its path here so the API version is defined once and the same resource
+//! is always addressed the same way.
+
+/// Version of the QStash API the client talks to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiVersion {
+    V2,
+}
+
+impl ApiVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiVersion::V2 => "v2",
+        }
+    }
+}
+
+pub const API_VERSION: ApiVersion = ApiVersion::V2;
+
+fn path(resource: &str) -> String {
+    format!("/{}/{}", API_VERSION.as_str(), resource)
+}
+
+pub(crate) fn publish(destination: &str) -> String {
+    path(&format!("publish/{}", destination))
+}
+
+pub(crate) fn enqueue(queue_name: &str, destination: &str) -> String {
+    path(&format!("enqueue/{}/{}", queue_name, destination))
+}
+
+pub(crate) fn batch() -> String {
+    path("batch")
+}
+
+pub(crate) fn messages() -> String {
+    path("messages")
+}
+
+pub(crate) fn message(message_id: &str) -> String {
+    path(&format!("messages/{}", message_id))
+}
+
+pub(crate) fn dlq() -> String {
+    path("dlq")
+}
+
+pub(crate) fn dlq_message(dlq_id: &str) -> String {
+    path(&format!("dlq/{}", dlq_id))
+}
+
+pub(crate) fn events() -> String {
+    path("events")
+}
+
+pub(crate) fn queues() -> String {
+    path("queues/")
+}
+
+pub(crate) fn queue(queue_name: &str) -> String {
+    path(&format!("queues/{}", queue_name))
+}
+
+pub(crate) fn pause_queue(queue_name: &str) -> String {
+    path(&format!("queues/{}/pause", queue_name))
+}
+
+pub(crate) fn resume_queue(queue_name: &str) -> String {
+    path(&format!("queues/{}/resume", queue_name))
+}
+
+pub(crate) fn schedules() -> String {
+    path("schedules")
+}
+
+/// Schedules are created under their destination and addressed by id afterwards.
+pub(crate) fn schedule(destination_or_id: &str) -> String {
+    path(&format!("schedules/{}", destination_or_id))
+}
+
+pub(crate) fn pause_schedule(schedule_id: &str) -> String {
+    path(&format!("schedules/{}/pause", schedule_id))
+}
+
+pub(crate) fn resume_schedule(schedule_id: &str) -> String {
+    path(&format!("schedules/{}/resume", schedule_id))
+}
+
+pub(crate) fn signing_keys() -> String {
+    path("keys")
+}
+
+pub(crate) fn rotate_signing_keys() -> String {
+    path("keys/rotate")
+}
+
+pub(crate) fn url_groups() -> String {
+    path("topics")
+}
+
+pub(crate) fn url_group(url_group_name: &str) -> String {
+    path(&format!("topics/{}", url_group_name))
+}
+
+pub(crate) fn url_group_endpoints(url_group_name: &str) -> String {
+    path(&format!("topics/{}/endpoints", url_group_name))
+}
+
+/// The LLM API is OpenAI compatible and versioned separately from the rest of QStash.
+pub(crate) fn chat_completions() -> String {
+    "/llm/v1/chat/completions".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_paths() {
+        assert_eq!(
+            publish("https://example.com/a"),
+            "/v2/publish/https://example.com/a"
+        );
+        assert_eq!(
+            enqueue("queue1", "https://example.com/a"),
+            "/v2/enqueue/queue1/https://example.com/a"
+        );
+        assert_eq!(batch(), "/v2/batch");
+        assert_eq!(messages(), "/v2/messages");
+        assert_eq!(message("msg1"), "/v2/messages/msg1");
+    }
+
+    #[test]
+    fn test_dlq_paths() {
+        assert_eq!(dlq(), "/v2/dlq");
+        assert_eq!(dlq_message("dlq1"), "/v2/dlq/dlq1");
+    }
+
+    #[test]
+    fn test_queue_paths() {
+        assert_eq!(queues(), "/v2/queues/");
+        assert_eq!(queue("queue1"), "/v2/queues/queue1");
+        assert_eq!(pause_queue("queue1"), "/v2/queues/queue1/pause");
+        assert_eq!(resume_queue("queue1"), "/v2/queues/queue1/resume");
+    }
+
+    #[test]
+    fn test_schedule_paths() {
+        assert_eq!(schedules(), "/v2/schedules");
+        assert_eq!(schedule("sched1"), "/v2/schedules/sched1");
+        assert_eq!(pause_schedule("sched1"), "/v2/schedules/sched1/pause");
+        assert_eq!(resume_schedule("sched1"), "/v2/schedules/sched1/resume");
+    }
+
+    #[test]
+    fn test_other_paths() {
+        assert_eq!(events(), "/v2/events");
+        assert_eq!(signing_keys(), "/v2/keys");
+        assert_eq!(rotate_signing_keys(), "/v2/keys/rotate");
+        assert_eq!(url_groups(), "/v2/topics");
+        assert_eq!(url_group("group1"), "/v2/topics/group1");
+        assert_eq!(url_group_endpoints("group1"), "/v2/topics/group1/endpoints");
+        assert_eq!(chat_completions(), "/llm/v1/chat/completions");
+        assert_eq!(API_VERSION.as_str(), "v2");
+    }
+}
@@ -1,11 +1,14 @@
 use std::error;
 use std::fmt;
 
+use crate::message_types::MessageResponseResult;
+
 #[derive(Debug)]
 pub enum QstashError {
     InvalidApiKey,
     InvalidBaseUrl(String),
     InvalidRequestUrl(String),
+    HttpClientBuildFailed(reqwest::Error),
     RequestFailed(reqwest::Error),
     ResponseBodyParseError(reqwest::Error),
     ResponseStreamParseError(serde_json::Error),
@@ -18,8 +21,111 @@ pub enum QstashError {
     ChatRateLimitExceeded {
         reset_requests: u64,
         reset_tokens: u64,
+        remaining_requests: u64,
+        remaining_tokens: u64,
     },
     UnspecifiedRateLimitExceeded,
+    /// An `Upstash-Signature` token is malformed and couldn't be decoded.
+    InvalidSignature(String),
+    /// An `Upstash-Signature` token was decoded but doesn't verify: its signature, issuer,
+    /// validity window or body hash doesn't match.
+    SignatureVerificationFailed(String),
+    LlmError {
+        /// The HTTP status of the failed response.
+        status: u16,
+        message: String,
+        kind: Option<String>,
+        code: Option<String>,
+    },
+    /// The chat completion request was rejected locally, before being sent.
+    InvalidChatCompletionRequest(String),
+    /// A header name or value couldn't be turned into a valid HTTP header.
+    InvalidHeader(String),
+    /// A URL group name is empty or contains characters QStash doesn't allow.
+    InvalidUrlGroupName(String),
+    /// A schedule was about to be created without a cron expression.
+    MissingCron,
+    /// A destructive operation was called without explicit confirmation.
+    ConfirmationRequired(String),
+    /// No delivery attempt of the probe message, identified by its id, was logged in time.
+    DestinationProbeTimedOut(String),
+    /// Some items of a bulk operation failed, keyed by the name or id of the item.
+    BulkOperationFailed(Vec<(String, QstashError)>),
+    /// The runtime driving a blocking client couldn't be started.
+    RuntimeBuildFailed(std::io::Error),
+    /// A verified callback body isn't a valid callback payload.
+    CallbackParseError(serde_json::Error),
+    /// Writing exported data to the caller's writer failed.
+    ExportWriteFailed(std::io::Error),
+    /// With strict deserialization on, a response body was malformed or had unknown fields.
+    /// Kept apart from `ResponseBodyParseError`, which wraps a `reqwest::Error` and can't carry
+    /// the `serde_json::Error` naming the offending fields.
+    StrictResponseParseError(serde_json::Error),
+    /// The caller cancelled the request before it completed.
+    Cancelled,
+    /// A base64 encoded body returned by QStash couldn't be decoded.
+    BodyDecodeError(base64::DecodeError),
+    /// A DLQ message was published again but couldn't be removed from the DLQ afterwards.
+    /// `response` is the response of the new publish, so the new message ids aren't lost.
+    DlqCleanupFailed {
+        response: MessageResponseResult,
+        error: Box<QstashError>,
+    },
+}
+
+/// Turns a 404 from QStash into `Ok(None)`, for lookups of resources that may be gone.
+pub(crate) fn none_if_not_found<T>(
+    result: Result<T, QstashError>,
+) -> Result<Option<T>, QstashError> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(QstashError::RequestFailed(err))
+            if err.status() == Some(reqwest::StatusCode::NOT_FOUND) =>
+        {
+            Ok(None)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+impl QstashError {
+    /// Suggests an HTTP status code to answer with when this error is surfaced by a web handler.
+    pub fn http_status(&self) -> u16 {
+        match self {
+            QstashError::InvalidApiKey => 401,
+            QstashError::InvalidBaseUrl(_) => 500,
+            QstashError::InvalidRequestUrl(_) => 400,
+            QstashError::HttpClientBuildFailed(_) => 500,
+            QstashError::RequestFailed(err) => match err.status().map(|status| status.as_u16()) {
+                Some(400) => 400,
+                Some(404) => 404,
+                _ => 502,
+            },
+            QstashError::ResponseBodyParseError(_) => 502,
+            QstashError::ResponseStreamParseError(_) => 502,
+            QstashError::DailyRateLimitExceeded { .. } => 429,
+            QstashError::BurstRateLimitExceeded { .. } => 429,
+            QstashError::ChatRateLimitExceeded { .. } => 429,
+            QstashError::UnspecifiedRateLimitExceeded => 429,
+            QstashError::InvalidSignature(_) => 401,
+            QstashError::SignatureVerificationFailed(_) => 401,
+            QstashError::LlmError { status, .. } => *status,
+            QstashError::InvalidChatCompletionRequest(_) => 400,
+            QstashError::InvalidHeader(_) => 400,
+            QstashError::InvalidUrlGroupName(_) => 400,
+            QstashError::MissingCron => 400,
+            QstashError::ConfirmationRequired(_) => 400,
+            QstashError::DestinationProbeTimedOut(_) => 504,
+            QstashError::BulkOperationFailed(_) => 502,
+            QstashError::RuntimeBuildFailed(_) => 500,
+            QstashError::CallbackParseError(_) => 400,
+            QstashError::ExportWriteFailed(_) => 500,
+            QstashError::StrictResponseParseError(_) => 502,
+            QstashError::Cancelled => 499,
+            QstashError::BodyDecodeError(_) => 502,
+            QstashError::DlqCleanupFailed { error, .. } => error.http_status(),
+        }
+    }
 }
 
 impl fmt::Display for QstashError {
@@ -28,6 +134,9 @@ impl fmt::Display for QstashError {
             QstashError::InvalidApiKey => write!(f, "Invalid API key"),
             QstashError::InvalidBaseUrl(url) => write!(f, "Invalid base URL: {}", url),
             QstashError::InvalidRequestUrl(url) => write!(f, "Invalid request URL: {}", url),
+            QstashError::HttpClientBuildFailed(err) => {
+                write!(f, "Failed to build HTTP client: {}", err)
+            }
             QstashError::RequestFailed(err) => write!(f, "Request failed: {}", err),
             QstashError::ResponseBodyParseError(err) => {
                 write!(f, "Failed to parse response body: {}", err)
@@ -44,14 +153,71 @@ impl fmt::Display for QstashError {
             QstashError::ChatRateLimitExceeded {
                 reset_requests,
                 reset_tokens,
+                remaining_requests,
+                remaining_tokens,
             } => write!(
                 f,
-                "Chat rate limit exceeded. Retry after requests reset: {}, tokens reset: {}",
-                reset_requests, reset_tokens
+                "Chat rate limit exceeded. Retry after requests reset: {}, tokens reset: {} (remaining requests: {}, tokens: {})",
+                reset_requests, reset_tokens, remaining_requests, remaining_tokens
             ),
             QstashError::UnspecifiedRateLimitExceeded => {
                 write!(f, "Rate limit exceeded, but no details provided")
             }
+            QstashError::InvalidSignature(reason) => write!(f, "Invalid signature: {}", reason),
+            QstashError::SignatureVerificationFailed(reason) => {
+                write!(f, "Signature verification failed: {}", reason)
+            }
+            QstashError::LlmError {
+                status,
+                message,
+                kind,
+                code,
+            } => write!(
+                f,
+                "LLM request failed with status {}: {} (type: {}, code: {})",
+                status,
+                message,
+                kind.as_deref().unwrap_or("unknown"),
+                code.as_deref().unwrap_or("unknown")
+            ),
+            QstashError::InvalidChatCompletionRequest(reason) => {
+                write!(f, "Invalid chat completion request: {}", reason)
+            }
+            QstashError::InvalidHeader(reason) => write!(f, "Invalid header: {}", reason),
+            QstashError::InvalidUrlGroupName(reason) => {
+                write!(f, "Invalid URL group name: {}", reason)
+            }
+            QstashError::MissingCron => write!(f, "Schedule has no cron expression"),
+            QstashError::ConfirmationRequired(operation) => {
+                write!(f, "Confirmation required to {}", operation)
+            }
+            QstashError::DestinationProbeTimedOut(message_id) => write!(
+                f,
+                "No delivery attempt logged for probe message {}",
+                message_id
+            ),
+            QstashError::BulkOperationFailed(errors) => {
+                write!(f, "{} operation(s) failed:", errors.len())?;
+                for (key, err) in errors {
+                    write!(f, " [{}: {}]", key, err)?;
+                }
+                Ok(())
+            }
+            QstashError::RuntimeBuildFailed(err) => {
+                write!(f, "Failed to build runtime: {}", err)
+            }
+            QstashError::CallbackParseError(err) => {
+                write!(f, "Failed to parse callback payload: {}", err)
+            }
+            QstashError::ExportWriteFailed(err) => write!(f, "Failed to write export: {}", err),
+            QstashError::StrictResponseParseError(err) => {
+                write!(f, "Failed to parse response body strictly: {}", err)
+            }
+            QstashError::Cancelled => write!(f, "Request was cancelled"),
+            QstashError::BodyDecodeError(err) => write!(f, "Failed to decode body: {}", err),
+            QstashError::DlqCleanupFailed { error, .. } => {
+                write!(f, "Resumed message could not be removed from the DLQ: {}", error)
+            }
         }
     }
 }
@@ -62,6 +228,7 @@ impl error::Error for QstashError {
             QstashError::InvalidApiKey => None,
             QstashError::InvalidBaseUrl(_) => None,
             QstashError::InvalidRequestUrl(_) => None,
+            QstashError::HttpClientBuildFailed(err) => Some(err),
             QstashError::RequestFailed(err) => Some(err),
             QstashError::ResponseBodyParseError(err) => Some(err),
             QstashError::ResponseStreamParseError(err) => Some(err),
@@ -69,6 +236,181 @@ impl error::Error for QstashError {
             QstashError::BurstRateLimitExceeded { .. } => None,
             QstashError::ChatRateLimitExceeded { .. } => None,
             QstashError::UnspecifiedRateLimitExceeded => None,
+            QstashError::InvalidSignature(_) => None,
+            QstashError::SignatureVerificationFailed(_) => None,
+            QstashError::LlmError { .. } => None,
+            QstashError::InvalidChatCompletionRequest(_) => None,
+            QstashError::InvalidHeader(_) => None,
+            QstashError::InvalidUrlGroupName(_) => None,
+            QstashError::MissingCron => None,
+            QstashError::ConfirmationRequired(_) => None,
+            QstashError::DestinationProbeTimedOut(_) => None,
+            QstashError::BulkOperationFailed(_) => None,
+            QstashError::RuntimeBuildFailed(err) => Some(err),
+            QstashError::CallbackParseError(err) => Some(err),
+            QstashError::ExportWriteFailed(err) => Some(err),
+            QstashError::StrictResponseParseError(err) => Some(err),
+            QstashError::Cancelled => None,
+            QstashError::BodyDecodeError(err) => Some(err),
+            QstashError::DlqCleanupFailed { error, .. } => Some(error.as_ref()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::QstashClient;
+    use httpmock::Method::GET;
+    use httpmock::MockServer;
+    use reqwest::Url;
+
+    async fn request_failed_with_status(status: u16) -> QstashError {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/v2/messages/msg123");
+            then.status(status);
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .build()
+            .expect("Failed to build QstashClient");
+        client.get_message("msg123").await.unwrap_err()
+    }
+
+    #[tokio::test]
+    async fn test_http_status_request_failed() {
+        assert_eq!(request_failed_with_status(400).await.http_status(), 400);
+        assert_eq!(request_failed_with_status(404).await.http_status(), 404);
+        assert_eq!(request_failed_with_status(500).await.http_status(), 502);
+
+        let builder_error = reqwest::Client::new()
+            .get("http://[invalid")
+            .build()
+            .unwrap_err();
+        assert_eq!(QstashError::RequestFailed(builder_error).http_status(), 502);
+    }
+
+    #[test]
+    fn test_http_status_mapping() {
+        let parse_error = || serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let reqwest_error = || {
+            reqwest::Client::new()
+                .get("http://[invalid")
+                .build()
+                .unwrap_err()
+        };
+
+        assert_eq!(QstashError::InvalidApiKey.http_status(), 401);
+        assert_eq!(
+            QstashError::InvalidBaseUrl(String::new()).http_status(),
+            500
+        );
+        assert_eq!(
+            QstashError::InvalidRequestUrl(String::new()).http_status(),
+            400
+        );
+        assert_eq!(
+            QstashError::HttpClientBuildFailed(reqwest_error()).http_status(),
+            500
+        );
+        assert_eq!(
+            QstashError::ResponseBodyParseError(reqwest_error()).http_status(),
+            502
+        );
+        assert_eq!(
+            QstashError::ResponseStreamParseError(parse_error()).http_status(),
+            502
+        );
+        assert_eq!(
+            QstashError::DailyRateLimitExceeded { reset: 0 }.http_status(),
+            429
+        );
+        assert_eq!(
+            QstashError::BurstRateLimitExceeded { reset: 0 }.http_status(),
+            429
+        );
+        assert_eq!(
+            QstashError::ChatRateLimitExceeded {
+                reset_requests: 0,
+                reset_tokens: 0,
+                remaining_requests: 0,
+                remaining_tokens: 0
+            }
+            .http_status(),
+            429
+        );
+        assert_eq!(QstashError::UnspecifiedRateLimitExceeded.http_status(), 429);
+        assert_eq!(
+            QstashError::InvalidSignature(String::new()).http_status(),
+            401
+        );
+        assert_eq!(
+            QstashError::SignatureVerificationFailed(String::new()).http_status(),
+            401
+        );
+        assert_eq!(
+            QstashError::LlmError {
+                status: 400,
+                message: String::new(),
+                kind: None,
+                code: None
+            }
+            .http_status(),
+            400
+        );
+        assert_eq!(
+            QstashError::InvalidChatCompletionRequest(String::new()).http_status(),
+            400
+        );
+        assert_eq!(QstashError::InvalidHeader(String::new()).http_status(), 400);
+        assert_eq!(
+            QstashError::InvalidUrlGroupName(String::new()).http_status(),
+            400
+        );
+        assert_eq!(QstashError::MissingCron.http_status(), 400);
+        assert_eq!(
+            QstashError::ConfirmationRequired(String::new()).http_status(),
+            400
+        );
+        assert_eq!(
+            QstashError::DestinationProbeTimedOut(String::new()).http_status(),
+            504
+        );
+        assert_eq!(
+            QstashError::BulkOperationFailed(vec![(String::new(), QstashError::MissingCron)])
+                .http_status(),
+            502
+        );
+        assert_eq!(
+            QstashError::RuntimeBuildFailed(std::io::Error::other("runtime")).http_status(),
+            500
+        );
+        assert_eq!(
+            QstashError::CallbackParseError(parse_error()).http_status(),
+            400
+        );
+        assert_eq!(
+            QstashError::ExportWriteFailed(std::io::Error::other("export")).http_status(),
+            500
+        );
+        assert_eq!(
+            QstashError::StrictResponseParseError(parse_error()).http_status(),
+            502
+        );
+        assert_eq!(QstashError::Cancelled.http_status(), 499);
+        assert_eq!(
+            QstashError::BodyDecodeError(base64::DecodeError::InvalidLength(1)).http_status(),
+            502
+        );
+        assert_eq!(
+            QstashError::DlqCleanupFailed {
+                response: MessageResponseResult::URLGroupResponse(Vec::new()),
+                error: Box::new(QstashError::UnspecifiedRateLimitExceeded),
+            }
+            .http_status(),
+            429
+        );
+    }
+}
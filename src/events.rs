@@ -1,46 +1,319 @@
-use reqwest::Method;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDate};
+use futures::stream::{self, Stream};
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::{Method, Url};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 
 use crate::client::QstashClient;
+use crate::endpoints;
 use crate::errors::QstashError;
-use crate::events_types::{EventsRequest, EventsResponse};
+use crate::events_types::{DeliveryStats, Event, EventsRequest, EventsResponse};
+use crate::message_types::MessageResponseResult;
+use crate::pagination::Page;
+
+/// Upper bound on the number of pages `delivery_stats` and `events_by_day` fetch before giving up.
+const MAX_EVENT_PAGES: usize = 100;
+
+/// The most events QStash returns in a single page.
+const MAX_EVENTS_PER_PAGE: usize = 1000;
+
+/// How often and how many times `test_destination` looks up the events of its probe message.
+const PROBE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const MAX_PROBE_POLLS: usize = 30;
 
 impl QstashClient {
     pub async fn list_events(&self, request: EventsRequest) -> Result<EventsResponse, QstashError> {
         let request = self
             .client
-            .get_request_builder(
-                Method::GET,
-                self.base_url
-                    .join("/v2/events")
-                    .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
-            )
+            .get_request_builder(Method::GET, self.endpoint_url(&endpoints::events())?)
             .query(&request.to_query_params());
 
-        let response = self
-            .client
-            .send_request(request)
-            .await?
-            .json::<EventsResponse>()
-            .await
-            .map_err(QstashError::ResponseBodyParseError)?;
+        let response = self.client.send_json::<EventsResponse>(request).await?;
 
         Ok(response)
     }
+
+    /// Pages through the events matching `request`, starting at its cursor, and yields them one
+    /// by one. The filters and `count` of `request` apply to every page.
+    ///
+    /// The next page is only fetched once the current one has been consumed. The stream ends
+    /// after the last page, or after yielding the error of a page that failed.
+    pub fn list_events_stream(
+        &self,
+        request: EventsRequest,
+    ) -> impl Stream<Item = Result<Event, QstashError>> + '_ {
+        let state = (Some(request), VecDeque::new());
+
+        stream::unfold(state, move |(mut request, mut pending)| async move {
+            loop {
+                if let Some(event) = pending.pop_front() {
+                    return Some((Ok(event), (request, pending)));
+                }
+
+                let mut current = request.take()?;
+                let page: Page<_> = match self.list_events(current.clone()).await {
+                    Ok(response) => response.into(),
+                    Err(err) => return Some((Err(err), (None, pending))),
+                };
+
+                if page.has_next_page() {
+                    current.cursor = page.cursor;
+                    request = Some(current);
+                }
+                pending.extend(page.items);
+            }
+        })
+    }
+
+    /// Fetches the `n` most recent events, newest first, in a single request without paginating.
+    /// `n` is capped at the 1000 events QStash returns per page.
+    pub async fn latest_events(&self, n: usize) -> Result<Vec<Event>, QstashError> {
+        let request = EventsRequest {
+            count: Some(n.min(MAX_EVENTS_PER_PAGE) as i32),
+            order: Some("latestFirst".to_string()),
+            ..Default::default()
+        };
+
+        Ok(self.list_events(request).await?.events)
+    }
+
+    /// Polls the latest events every `poll_interval` and yields the ones that weren't there on
+    /// the previous poll, oldest first, like `tail -f` for deliveries. Events that existed before
+    /// the first poll aren't yielded.
+    ///
+    /// Events are told apart by message id and time. Each poll only sees the latest page, so if
+    /// more events than fit in a page arrive between two polls, the oldest of them are missed. A
+    /// failed poll yields its error and the stream keeps polling.
+    pub fn tail_events(
+        &self,
+        poll_interval: Duration,
+    ) -> impl Stream<Item = Result<Event, QstashError>> + '_ {
+        let state = TailState {
+            seen: None,
+            pending: VecDeque::new(),
+            polled: false,
+        };
+
+        stream::unfold(state, move |mut state| async move {
+            loop {
+                if let Some(event) = state.pending.pop_front() {
+                    return Some((Ok(event), state));
+                }
+
+                if state.polled {
+                    tokio::time::sleep(poll_interval).await;
+                }
+                state.polled = true;
+
+                let request = EventsRequest {
+                    order: Some("latestFirst".to_string()),
+                    ..Default::default()
+                };
+                let events = match self.list_events(request).await {
+                    Ok(response) => response.events,
+                    Err(err) => return Some((Err(err), state)),
+                };
+
+                let keys = events.iter().map(tail_key).collect();
+                if let Some(seen) = &state.seen {
+                    let new_events = events
+                        .into_iter()
+                        .rev()
+                        .filter(|event| !seen.contains(&tail_key(event)));
+                    state.pending.extend(new_events);
+                }
+                state.seen = Some(keys);
+            }
+        })
+    }
+
+    /// Pages through the events between `from` and `to` (Unix milliseconds, both inclusive) and
+    /// counts deliveries, failures and retries.
+    /// Stops after `MAX_EVENT_PAGES` pages, so very busy windows are only partially counted.
+    pub async fn delivery_stats(&self, from: i64, to: i64) -> Result<DeliveryStats, QstashError> {
+        let mut stats = DeliveryStats::default();
+        let mut cursor = None;
+
+        for _ in 0..MAX_EVENT_PAGES {
+            let request = EventsRequest {
+                cursor,
+                from_date: Some(from),
+                to_date: Some(to),
+                ..Default::default()
+            };
+            let page: Page<_> = self.list_events(request).await?.into();
+            page.iter().for_each(|event| stats.record(&event.state));
+
+            cursor = page.cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Pages through the events between `from` and `to` (Unix milliseconds, both inclusive) and
+    /// groups them by the UTC day they happened on.
+    /// Stops after `MAX_EVENT_PAGES` pages, so very busy windows are only partially returned.
+    pub async fn events_by_day(
+        &self,
+        from: i64,
+        to: i64,
+    ) -> Result<BTreeMap<NaiveDate, Vec<Event>>, QstashError> {
+        let mut days: BTreeMap<NaiveDate, Vec<Event>> = BTreeMap::new();
+        let mut cursor = None;
+
+        for _ in 0..MAX_EVENT_PAGES {
+            let request = EventsRequest {
+                cursor,
+                from_date: Some(from),
+                to_date: Some(to),
+                ..Default::default()
+            };
+            let page: Page<_> = self.list_events(request).await?.into();
+            cursor = page.cursor;
+
+            for event in page.items {
+                if let Some(time) = DateTime::from_timestamp_millis(event.time) {
+                    days.entry(time.date_naive()).or_default().push(event);
+                }
+            }
+
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        Ok(days)
+    }
+
+    /// Pages through the events matching `request`, starting at its cursor, and writes each
+    /// event to `writer` as one line of JSON. Returns the number of events written.
+    ///
+    /// Events are written page by page as they arrive, so exports of any size only hold a
+    /// single page in memory.
+    pub async fn export_events_ndjson<W>(
+        &self,
+        mut request: EventsRequest,
+        mut writer: W,
+    ) -> Result<usize, QstashError>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let mut written = 0;
+
+        loop {
+            let page: Page<_> = self.list_events(request.clone()).await?.into();
+            for event in page.iter() {
+                let mut line = serde_json::to_vec(event)
+                    .map_err(|e| QstashError::ExportWriteFailed(e.into()))?;
+                line.push(b'\n');
+                writer
+                    .write_all(&line)
+                    .await
+                    .map_err(QstashError::ExportWriteFailed)?;
+                written += 1;
+            }
+
+            request.cursor = page.cursor;
+            if request.cursor.is_none() {
+                break;
+            }
+        }
+
+        writer
+            .flush()
+            .await
+            .map_err(QstashError::ExportWriteFailed)?;
+        Ok(written)
+    }
+
+    /// Publishes a small probe message to `url`, without retries and with a short timeout, and
+    /// returns the HTTP status the destination answered with.
+    ///
+    /// Useful to check that a URL works before creating a recurring schedule for it. The probe is
+    /// a real delivery, so the destination receives a `text/plain` request. Fails with
+    /// `QstashError::DestinationProbeTimedOut` if no delivery attempt is logged within about
+    /// 15 seconds.
+    ///
+    /// Only single http(s) URLs can be probed: URL group names fail with
+    /// `QstashError::InvalidRequestUrl` before anything is published, as does a publish that
+    /// doesn't return exactly one message id.
+    pub async fn test_destination(&self, url: &str) -> Result<u16, QstashError> {
+        let invalid_url = || QstashError::InvalidRequestUrl(url.to_string());
+        if !Url::parse(url).is_ok_and(|parsed| matches!(parsed.scheme(), "http" | "https")) {
+            return Err(invalid_url());
+        }
+
+        let mut headers = HeaderMap::new();
+        headers.insert("Upstash-Retries", HeaderValue::from_static("0"));
+        headers.insert("Upstash-Timeout", HeaderValue::from_static("10s"));
+
+        let message_id = match self
+            .publish_message(url, headers, "qstash-rs destination probe")
+            .await?
+        {
+            MessageResponseResult::URLResponse(response) if !response.message_id.is_empty() => {
+                response.message_id
+            }
+            _ => return Err(invalid_url()),
+        };
+
+        for poll in 0..MAX_PROBE_POLLS {
+            if poll > 0 {
+                tokio::time::sleep(PROBE_POLL_INTERVAL).await;
+            }
+
+            let request = EventsRequest {
+                message_id: Some(message_id.clone()),
+                ..Default::default()
+            };
+            let response = self.list_events(request).await?;
+            if let Some(status) = response
+                .events
+                .iter()
+                .find_map(|event| event.response_status)
+            {
+                return Ok(status);
+            }
+        }
+
+        Err(QstashError::DestinationProbeTimedOut(message_id))
+    }
+}
+
+// What `tail_events` carries from one poll to the next.
+struct TailState {
+    /// The events of the previous poll, `None` until the first successful poll.
+    seen: Option<HashSet<(String, i64)>>,
+    /// New events not yielded yet, oldest first.
+    pending: VecDeque<Event>,
+    polled: bool,
+}
+
+fn tail_key(event: &Event) -> (String, i64) {
+    (event.message_id.clone(), event.time)
 }
 
 #[cfg(test)]
 mod tests {
     use crate::client::QstashClient;
     use crate::errors::QstashError;
+    use crate::events_types::DeliveryStats;
     use crate::events_types::Event;
     use crate::events_types::EventState;
     use crate::events_types::EventsRequest;
     use crate::events_types::EventsResponse;
-    use httpmock::Method::GET;
+    use httpmock::Method::{GET, POST};
     use httpmock::MockServer;
     use reqwest::StatusCode;
     use reqwest::Url;
     use std::collections::HashMap;
+    use std::time::Duration;
 
     #[tokio::test]
     async fn test_list_events_success() {
@@ -82,6 +355,7 @@ mod tests {
                 endpoint_name: Some(1),
                 schedule_id: Some("sched1".to_string()),
                 queue_name: Some("queue1".to_string()),
+                response_status: None,
             }],
         };
         let list_mock = server.mock(|when, then| {
@@ -214,4 +488,438 @@ mod tests {
             Err(QstashError::ResponseBodyParseError(_))
         ));
     }
+
+    #[tokio::test]
+    async fn test_delivery_stats_aggregates_pages() {
+        let server = MockServer::start();
+        let first_page_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/events")
+                .header("Authorization", "Bearer test_api_key")
+                .query_param("fromDate", "1000")
+                .query_param("toDate", "2000")
+                .matches(|req| {
+                    req.query_params
+                        .as_ref()
+                        .is_none_or(|params| params.iter().all(|(key, _)| key != "cursor"))
+                });
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!({
+                    "cursor": "page2",
+                    "events": [
+                        {"messageId": "msg1", "state": "CREATED"},
+                        {"messageId": "msg1", "state": "DELIVERED"},
+                        {"messageId": "msg2", "state": "RETRY"},
+                        {"messageId": "msg2", "state": "DELIVERED"}
+                    ]
+                }));
+        });
+        let second_page_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/events")
+                .header("Authorization", "Bearer test_api_key")
+                .query_param("fromDate", "1000")
+                .query_param("toDate", "2000")
+                .query_param("cursor", "page2");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!({
+                    "events": [
+                        {"messageId": "msg3", "state": "RETRY"},
+                        {"messageId": "msg3", "state": "FAILED"},
+                        {"messageId": "msg4", "state": "DELIVERED"}
+                    ]
+                }));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let result = client.delivery_stats(1000, 2000).await;
+        first_page_mock.assert();
+        second_page_mock.assert();
+        let stats = result.unwrap();
+        assert_eq!(
+            stats,
+            DeliveryStats {
+                delivered: 3,
+                failed: 1,
+                retried: 2,
+            }
+        );
+        assert_eq!(stats.total(), 4);
+        assert_eq!(stats.success_ratio(), Some(0.75));
+        assert_eq!(DeliveryStats::default().success_ratio(), None);
+    }
+
+    #[tokio::test]
+    async fn test_test_destination_returns_response_status() {
+        let server = MockServer::start();
+        let publish_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/publish/https://example.com/webhook")
+                .header("Authorization", "Bearer test_api_key")
+                .header("Upstash-Retries", "0");
+            then.status(StatusCode::OK.as_u16())
+                .json_body(json!({"messageId": "msg_probe"}));
+        });
+        let events_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/events")
+                .header("Authorization", "Bearer test_api_key")
+                .query_param("messageId", "msg_probe");
+            then.status(StatusCode::OK.as_u16()).json_body(json!({
+                "events": [
+                    {"messageId": "msg_probe", "state": "CREATED"},
+                    {"messageId": "msg_probe", "state": "ACTIVE"},
+                    {"messageId": "msg_probe", "state": "ERROR", "responseStatus": 404}
+                ]
+            }));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let result = client.test_destination("https://example.com/webhook").await;
+
+        publish_mock.assert();
+        events_mock.assert();
+        assert_eq!(result.unwrap(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_export_events_ndjson() {
+        let server = MockServer::start();
+        let first_page_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/events")
+                .header("Authorization", "Bearer test_api_key")
+                .query_param("queueName", "queue1")
+                .matches(|req| {
+                    req.query_params
+                        .as_ref()
+                        .is_none_or(|params| params.iter().all(|(key, _)| key != "cursor"))
+                });
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!({
+                    "cursor": "page2",
+                    "events": [
+                        {"messageId": "msg1", "state": "CREATED"},
+                        {"messageId": "msg1", "state": "DELIVERED"}
+                    ]
+                }));
+        });
+        let second_page_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/events")
+                .header("Authorization", "Bearer test_api_key")
+                .query_param("queueName", "queue1")
+                .query_param("cursor", "page2");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!({
+                    "events": [{"messageId": "msg2", "state": "FAILED"}]
+                }));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let request = EventsRequest {
+            queue_name: Some("queue1".to_string()),
+            ..Default::default()
+        };
+
+        let mut buffer = Vec::new();
+        let written = client
+            .export_events_ndjson(request, &mut buffer)
+            .await
+            .unwrap();
+
+        first_page_mock.assert();
+        second_page_mock.assert();
+        assert_eq!(written, 3);
+        let output = String::from_utf8(buffer).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 3);
+        let last: Event = serde_json::from_str(lines[2]).unwrap();
+        assert_eq!(last.message_id, "msg2");
+        assert_eq!(last.state, EventState::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_events_by_day() {
+        let server = MockServer::start();
+        let first_page_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/events")
+                .header("Authorization", "Bearer test_api_key")
+                .query_param("fromDate", "1735689600000")
+                .query_param("toDate", "1735862399999")
+                .matches(|req| {
+                    req.query_params
+                        .as_ref()
+                        .is_none_or(|params| params.iter().all(|(key, _)| key != "cursor"))
+                });
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!({
+                    "cursor": "page2",
+                    "events": [
+                        // 2025-01-01T00:00:00Z and 2025-01-01T23:59:59.999Z
+                        {"messageId": "msg1", "time": 1735689600000i64, "state": "DELIVERED"},
+                        {"messageId": "msg2", "time": 1735775999999i64, "state": "FAILED"}
+                    ]
+                }));
+        });
+        let second_page_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/events")
+                .header("Authorization", "Bearer test_api_key")
+                .query_param("cursor", "page2");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!({
+                    "events": [
+                        // 2025-01-02T00:00:00Z
+                        {"messageId": "msg3", "time": 1735776000000i64, "state": "DELIVERED"}
+                    ]
+                }));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let days = client
+            .events_by_day(1735689600000, 1735862399999)
+            .await
+            .unwrap();
+
+        first_page_mock.assert();
+        second_page_mock.assert();
+        let day = |d| chrono::NaiveDate::from_ymd_opt(2025, 1, d).unwrap();
+        let ids = |d| -> Vec<&str> {
+            days[&day(d)]
+                .iter()
+                .map(|event| event.message_id.as_str())
+                .collect()
+        };
+        assert_eq!(days.len(), 2);
+        assert_eq!(ids(1), vec!["msg1", "msg2"]);
+        assert_eq!(ids(2), vec!["msg3"]);
+    }
+
+    #[tokio::test]
+    async fn test_latest_events() {
+        let server = MockServer::start();
+        let latest_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/events")
+                .query_param("count", "5")
+                .query_param("order", "latestFirst")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!({
+                    "cursor": "next_page",
+                    "events": [
+                        {"time": 2, "messageId": "msg2", "state": "DELIVERED"},
+                        {"time": 1, "messageId": "msg1", "state": "CREATED"}
+                    ]
+                }));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let events = client.latest_events(5).await.unwrap();
+
+        latest_mock.assert();
+        let message_ids: Vec<_> = events
+            .iter()
+            .map(|event| event.message_id.as_str())
+            .collect();
+        assert_eq!(message_ids, vec!["msg2", "msg1"]);
+    }
+
+    #[tokio::test]
+    async fn test_tail_events_yields_new_events() {
+        use futures::StreamExt;
+
+        let server = MockServer::start();
+        let first_poll = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/events")
+                .query_param("order", "latestFirst");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!({
+                    "events": [{"time": 1, "messageId": "msg1", "state": "CREATED"}]
+                }));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let mut events = Box::pin(client.tail_events(Duration::from_millis(50)));
+
+        // Swaps the events returned once the first poll has been answered.
+        let swap_events = async {
+            while first_poll.hits_async().await == 0 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+            first_poll.delete_async().await;
+            server.mock(|when, then| {
+                when.method(GET)
+                    .path("/v2/events")
+                    .query_param("order", "latestFirst");
+                then.status(StatusCode::OK.as_u16())
+                    .header("Content-Type", "application/json")
+                    .json_body(json!({
+                        "events": [
+                            {"time": 3, "messageId": "msg2", "state": "CREATED"},
+                            {"time": 1, "messageId": "msg1", "state": "CREATED"}
+                        ]
+                    }));
+            })
+        };
+        let (event, second_poll) = tokio::join!(events.next(), swap_events);
+
+        let event = event.unwrap().unwrap();
+        assert_eq!(event.message_id, "msg2");
+        assert_eq!(event.time, 3);
+        assert!(second_poll.hits() >= 1);
+    }
+
+    #[tokio::test]
+    async fn test_list_events_stream_follows_cursor() {
+        use futures::{StreamExt, TryStreamExt};
+
+        let server = MockServer::start();
+        let first_page_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/events")
+                .query_param("state", "DELIVERED")
+                .query_param("count", "2")
+                .matches(|req| {
+                    req.query_params
+                        .as_ref()
+                        .is_none_or(|params| params.iter().all(|(key, _)| key != "cursor"))
+                });
+            then.status(StatusCode::OK.as_u16()).json_body(json!({
+                "cursor": "page2",
+                "events": [{"messageId": "msg1"}, {"messageId": "msg2"}]
+            }));
+        });
+        let second_page_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/events")
+                .query_param("state", "DELIVERED")
+                .query_param("count", "2")
+                .query_param("cursor", "page2");
+            then.status(StatusCode::OK.as_u16()).json_body(json!({
+                "events": [{"messageId": "msg3"}]
+            }));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let request = EventsRequest {
+            state: Some("DELIVERED".to_string()),
+            count: Some(2),
+            ..Default::default()
+        };
+
+        let mut events = Box::pin(client.list_events_stream(request));
+        let first = events.next().await.unwrap().unwrap();
+        assert_eq!(first.message_id, "msg1");
+        first_page_mock.assert();
+        // The second page isn't fetched before the first one has been consumed.
+        second_page_mock.assert_hits(0);
+
+        let rest: Vec<Event> = events.try_collect().await.unwrap();
+        second_page_mock.assert();
+        let message_ids: Vec<_> = rest.iter().map(|event| event.message_id.as_str()).collect();
+        assert_eq!(message_ids, vec!["msg2", "msg3"]);
+    }
+
+    #[tokio::test]
+    async fn test_test_destination_rejects_url_groups() {
+        let server = MockServer::start();
+        let publish_mock = server.mock(|when, then| {
+            when.method(POST).path_contains("/v2/publish/");
+            then.status(StatusCode::OK.as_u16())
+                .json_body(json!([{"messageId": "msg1", "url": "https://example.com/1"}]));
+        });
+        let events_mock = server.mock(|when, then| {
+            when.method(GET).path("/v2/events");
+            then.status(StatusCode::OK.as_u16())
+                .json_body(json!({"events": []}));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let result = client.test_destination("my-url-group").await;
+        assert!(matches!(result, Err(QstashError::InvalidRequestUrl(_))));
+        publish_mock.assert_hits(0);
+
+        // A fan-out response has no single message to follow.
+        let result = client.test_destination("https://example.com/1").await;
+        assert!(matches!(result, Err(QstashError::InvalidRequestUrl(_))));
+        publish_mock.assert_hits(1);
+        events_mock.assert_hits(0);
+    }
+
+    #[tokio::test]
+    async fn test_test_destination_requires_message_id() {
+        let server = MockServer::start();
+        let publish_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/publish/https://example.com/webhook");
+            then.status(StatusCode::OK.as_u16())
+                .json_body(json!({"messageId": ""}));
+        });
+        let events_mock = server.mock(|when, then| {
+            when.method(GET).path("/v2/events");
+            then.status(StatusCode::OK.as_u16()).json_body(json!({
+                "events": [{"messageId": "unrelated", "state": "ERROR", "responseStatus": 500}]
+            }));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let result = client.test_destination("https://example.com/webhook").await;
+
+        publish_mock.assert();
+        events_mock.assert_hits(0);
+        assert!(matches!(result, Err(QstashError::InvalidRequestUrl(_))));
+    }
 }
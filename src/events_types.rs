@@ -1,7 +1,10 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
 
-#[derive(Debug, Default)]
+use crate::message_types::deserialize_id;
+
+#[derive(Debug, Default, Clone)]
 pub struct EventsRequest {
     /// By providing a cursor you can paginate through all of the events.
     pub cursor: Option<String>,
@@ -78,6 +81,8 @@ impl EventsRequest {
 #[serde(rename_all = "camelCase", default)]
 pub struct EventsResponse {
     /// A cursor which you can use in subsequent requests to paginate through all events. If no cursor is returned, you have reached the end of the events.
+    /// Some responses name it `nextCursor`.
+    #[serde(alias = "nextCursor")]
     pub cursor: Option<String>,
     pub events: Vec<Event>,
 }
@@ -88,6 +93,7 @@ pub struct Event {
     /// Timestamp of this log entry, in milliseconds
     pub time: i64,
     /// The associated message id
+    #[serde(deserialize_with = "deserialize_id")]
     pub message_id: String,
     /// The headers of the message.
     pub header: HashMap<String, Vec<String>>,
@@ -115,6 +121,44 @@ pub struct Event {
     pub schedule_id: Option<String>,
     /// The name of the queue if this message is enqueued on a queue
     pub queue_name: Option<String>,
+    /// The HTTP status the destination answered with, on delivery attempts
+    pub response_status: Option<u16>,
+}
+
+/// Delivery outcomes counted over a time window by `delivery_stats`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DeliveryStats {
+    /// Number of `DELIVERED` events.
+    pub delivered: u64,
+    /// Number of `FAILED` events.
+    pub failed: u64,
+    /// Number of `RETRY` events.
+    pub retried: u64,
+}
+
+impl DeliveryStats {
+    pub(crate) fn record(&mut self, state: &EventState) {
+        match state {
+            EventState::Delivered => self.delivered += 1,
+            EventState::Failed => self.failed += 1,
+            EventState::Retry => self.retried += 1,
+            _ => {}
+        }
+    }
+
+    /// Number of messages that reached a final outcome, either delivered or failed.
+    pub fn total(&self) -> u64 {
+        self.delivered + self.failed
+    }
+
+    /// Share of final outcomes that were deliveries, between 0 and 1.
+    /// Returns `None` when nothing was delivered or failed in the window.
+    pub fn success_ratio(&self) -> Option<f64> {
+        match self.total() {
+            0 => None,
+            total => Some(self.delivered as f64 / total as f64),
+        }
+    }
 }
 
 #[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
@@ -144,7 +188,7 @@ fn serialize_body<S>(body: &[u8], serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
-    serializer.serialize_str(&base64::encode(body))
+    serializer.serialize_str(&STANDARD.encode(body))
 }
 
 fn deserialize_body<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
@@ -153,7 +197,7 @@ where
 {
     use serde::de::Error;
     String::deserialize(deserializer)
-        .and_then(|string| base64::decode(string.as_bytes()).map_err(Error::custom))
+        .and_then(|string| STANDARD.decode(string.as_bytes()).map_err(Error::custom))
 }
 
 // Example usage
@@ -289,6 +333,13 @@ mod tests {
         assert!(matches!(event.url, Some(ref url) if url == "https://example.com"));
     }
 
+    #[test]
+    fn test_deserialize_response_next_cursor() {
+        let response: EventsResponse =
+            serde_json::from_str(r#"{"nextCursor": "next_page", "events": []}"#).unwrap();
+        assert_eq!(response.cursor, Some("next_page".to_string()));
+    }
+
     #[test]
     fn test_deserialize_minimal_event() {
         let json_str = json!({
@@ -323,6 +374,7 @@ mod tests {
             endpoint_name: None,
             schedule_id: None,
             queue_name: None,
+            response_status: None,
         };
 
         let serialized = serde_json::to_string(&event).unwrap();
@@ -348,6 +400,7 @@ mod tests {
             endpoint_name: None,
             schedule_id: None,
             queue_name: None,
+            response_status: None,
         };
 
         let serialized = serde_json::to_string(&event).unwrap();
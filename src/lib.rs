@@ -1,8 +1,12 @@
 #[macro_use]
 extern crate serde_json;
 
+#[cfg(feature = "blocking")]
+pub mod blocking;
 pub mod client;
+pub mod config;
 pub mod dead_letter_queue;
+pub mod endpoints;
 pub mod errors;
 pub mod events;
 pub mod events_types;
@@ -10,8 +14,11 @@ pub mod llm;
 pub mod llm_types;
 pub mod message_types;
 pub mod messages;
+pub mod pagination;
+pub mod paused;
 pub mod queues;
 pub mod rate_limited_client;
 pub mod schedules;
 pub mod signing_keys;
+pub mod status;
 pub mod url_groups;
@@ -1,9 +1,13 @@
-use reqwest::Method;
+use std::time::Duration;
+
+use reqwest::{Method, StatusCode};
+use tokio_util::sync::CancellationToken;
 
 use crate::client::QstashClient;
+use crate::endpoints;
 use crate::errors::QstashError;
 use crate::llm_types::{
-    ChatCompletionRequest, ChatCompletionResponse, DirectResponse, StreamResponse,
+    ChatCompletionRequest, ChatCompletionResponse, DirectResponse, LlmErrorResponse, StreamResponse,
 };
 
 impl QstashClient {
@@ -11,17 +15,57 @@ impl QstashClient {
         &self,
         chat_completion_request: ChatCompletionRequest,
     ) -> Result<ChatCompletionResponse, QstashError> {
-        let request = self
+        self.send_chat_completion(chat_completion_request, None).await
+    }
+
+    /// Same as `create_chat_completion`, but aborts with `QstashError::RequestFailed` if the
+    /// model doesn't respond within `timeout`. The deadline covers the whole response, so for
+    /// streaming requests it also bounds how long the stream may be read.
+    pub async fn create_chat_completion_with_timeout(
+        &self,
+        chat_completion_request: ChatCompletionRequest,
+        timeout: Duration,
+    ) -> Result<ChatCompletionResponse, QstashError> {
+        self.send_chat_completion(chat_completion_request, Some(timeout)).await
+    }
+
+    /// Same as `create_chat_completion`, but returns `QstashError::Cancelled` and drops the
+    /// in-flight request as soon as `cancellation_token` is cancelled. For streaming requests the
+    /// token only covers the request itself, drop the returned stream to stop reading it.
+    pub async fn create_chat_completion_with_cancellation(
+        &self,
+        chat_completion_request: ChatCompletionRequest,
+        cancellation_token: &CancellationToken,
+    ) -> Result<ChatCompletionResponse, QstashError> {
+        cancellation_token
+            .run_until_cancelled(self.send_chat_completion(chat_completion_request, None))
+            .await
+            .unwrap_or(Err(QstashError::Cancelled))
+    }
+
+    async fn send_chat_completion(
+        &self,
+        chat_completion_request: ChatCompletionRequest,
+        timeout: Option<Duration>,
+    ) -> Result<ChatCompletionResponse, QstashError> {
+        chat_completion_request.validate()?;
+
+        let mut request = self
             .client
             .get_request_builder(
                 Method::POST,
-                self.base_url
-                    .join("/llm/v1/chat/completions")
-                    .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
+                self.endpoint_url(&endpoints::chat_completions())?,
             )
             .json(&chat_completion_request);
 
-        let response = self.client.send_request(request).await?;
+        if let Some(timeout) = timeout {
+            request = request.timeout(timeout);
+        }
+
+        let response = self
+            .client
+            .send_request_with_error_parser(request, parse_llm_error)
+            .await?;
 
         match chat_completion_request.stream {
             Some(true) => {
@@ -30,16 +74,19 @@ impl QstashClient {
                 )))
             }
             _ => {
-                let response = response
-                    .json::<DirectResponse>()
-                    .await
-                    .map_err(QstashError::ResponseBodyParseError)?;
+                let response = self.client.parse_json::<DirectResponse>(response).await?;
                 Ok(ChatCompletionResponse::Direct(response))
             }
         }
     }
 }
 
+// The LLM endpoint describes its failures in an OpenAI-style error body.
+fn parse_llm_error(status: StatusCode, body: &[u8]) -> Option<QstashError> {
+    let response = serde_json::from_slice::<LlmErrorResponse>(body).ok()?;
+    Some(response.into_error(status.as_u16()))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::client::QstashClient;
@@ -49,6 +96,7 @@ mod tests {
     use httpmock::MockServer;
     use reqwest::StatusCode;
     use reqwest::Url;
+    use std::time::Duration;
 
     #[tokio::test]
     async fn test_chat_completion_direct_success() {
@@ -57,7 +105,7 @@ mod tests {
             model: "gpt-4".to_string(),
             messages: vec![Message {
                 role: "user".to_string(),
-                content: "Hello".to_string(),
+                content: "Hello".into(),
                 name: None,
             }],
             frequency_penalty: Some(0.5),
@@ -73,15 +121,17 @@ mod tests {
             seed: Some(42),
             stop: Some(vec!["\n".to_string()]),
             stream: Some(false),
+            stream_options: None,
             temperature: Some(0.7),
             top_p: Some(0.9),
+            user: None,
         };
         let expected_response = DirectResponse {
             id: "chatcmpl-123".to_string(),
             choices: vec![Choice {
                 message: Message {
                     role: "assistant".to_string(),
-                    content: "Hello! How can I assist you today?".to_string(),
+                    content: "Hello! How can I assist you today?".into(),
                     name: None,
                 },
                 finish_reason: Some("stop".to_string()),
@@ -144,7 +194,7 @@ mod tests {
             model: "gpt-4".to_string(),
             messages: vec![Message {
                 role: "user".to_string(),
-                content: "Hello".to_string(),
+                content: "Hello".into(),
                 name: None,
             }],
             frequency_penalty: Some(0.5),
@@ -160,8 +210,10 @@ mod tests {
             seed: Some(42),
             stop: Some(vec!["\n".to_string()]),
             stream: Some(false),
+            stream_options: None,
             temperature: Some(0.7),
             top_p: Some(0.9),
+            user: None,
         };
         let direct_mock = server.mock(|when, then| {
             when.method(POST)
@@ -194,7 +246,7 @@ mod tests {
             model: "gpt-4".to_string(),
             messages: vec![Message {
                 role: "user".to_string(),
-                content: "Hello".to_string(),
+                content: "Hello".into(),
                 name: None,
             }],
             frequency_penalty: Some(0.5),
@@ -210,8 +262,10 @@ mod tests {
             seed: Some(42),
             stop: Some(vec!["\n".to_string()]),
             stream: Some(false),
+            stream_options: None,
             temperature: Some(0.7),
             top_p: Some(0.9),
+            user: None,
         };
         let direct_mock = server.mock(|when, then| {
             when.method(POST)
@@ -243,7 +297,7 @@ mod tests {
             model: "gpt-4".to_string(),
             messages: vec![Message {
                 role: "user".to_string(),
-                content: "Hello".to_string(),
+                content: "Hello".into(),
                 name: None,
             }],
             frequency_penalty: Some(0.5),
@@ -259,8 +313,10 @@ mod tests {
             seed: Some(42),
             stop: Some(vec!["\n".to_string()]),
             stream: Some(true),
+            stream_options: None,
             temperature: Some(0.7),
             top_p: Some(0.9),
+            user: None,
         };
         let stream_response = "data: {\"id\": \"chatcmpl-123\", \"object\": \"chat.completion.chunk\", \"created\": 1625097600, \"model\": \"gpt-4\", \"choices\": [{\"delta\": {\"content\": \"Hello\"}, \"finish_reason\": null, \"index\": 0, \"logprobs\": null}]}\n\n\
         data: {\"id\": \"chatcmpl-123\", \"object\": \"chat.completion.chunk\", \"created\": 1625097600, \"model\": \"gpt-4\", \"choices\": [{\"delta\": {\"content\": \" World\"}, \"finish_reason\": null, \"index\": 0, \"logprobs\": null}]}\n\n\
@@ -300,7 +356,7 @@ mod tests {
             Some(" World".to_string())
         );
 
-        drop(stream_mock);
+        stream_mock.assert();
     }
 
     #[tokio::test]
@@ -310,7 +366,7 @@ mod tests {
             model: "gpt-4".to_string(),
             messages: vec![Message {
                 role: "user".to_string(),
-                content: "Hello".to_string(),
+                content: "Hello".into(),
                 name: None,
             }],
             frequency_penalty: Some(0.5),
@@ -326,8 +382,10 @@ mod tests {
             seed: Some(42),
             stop: Some(vec!["\n".to_string()]),
             stream: Some(true),
+            stream_options: None,
             temperature: Some(0.7),
             top_p: Some(0.9),
+            user: None,
         };
         let stream_mock = server.mock(|when, then| {
             when.method(POST)
@@ -360,7 +418,7 @@ mod tests {
             model: "gpt-4".to_string(),
             messages: vec![Message {
                 role: "user".to_string(),
-                content: "Hello".to_string(),
+                content: "Hello".into(),
                 name: None,
             }],
             frequency_penalty: Some(0.5),
@@ -376,8 +434,10 @@ mod tests {
             seed: Some(42),
             stop: Some(vec!["\n".to_string()]),
             stream: Some(true),
+            stream_options: None,
             temperature: Some(0.7),
             top_p: Some(0.9),
+            user: None,
         };
         let stream_mock = server.mock(|when, then| {
             when.method(POST)
@@ -413,7 +473,7 @@ mod tests {
             model: "gpt-4".to_string(),
             messages: vec![Message {
                 role: "user".to_string(),
-                content: "Hello".to_string(),
+                content: "Hello".into(),
                 name: None,
             }],
             frequency_penalty: Some(0.5),
@@ -429,8 +489,10 @@ mod tests {
             seed: Some(42),
             stop: Some(vec!["\n".to_string()]),
             stream: Some(true),
+            stream_options: None,
             temperature: Some(0.7),
             top_p: Some(0.9),
+            user: None,
         };
         let stream_response = "data: {\n  \"id\": \"chatcmpl-123\",\n  \"object\": \"chat.completion.chunk\",\n  \"created\": 1625097600,\n  \"model\": \"gpt-4\",\n  \"choices\": [\n    {\n      \"delta\": {\n        \"content\": \"Hello\"\n      },\n      \"finish_reason\": null,\n      \"index\": 0,\n      \"logprobs\": null\n    }\n  ]\n}\n\ndata: {\n  \"id\": \"chatcmpl-123\",\n  \"object\": \"chat.completion.chunk\",\n  \"created\": 1625097600,\n  \"model\": \"gpt-4\",\n  \"choices\": [\n    {\n      \"delta\": {\n        \"content\": \" World\"\n      },\n      \"finish_reason\": null,\n      \"index\": 0,\n      \"logprobs\": null\n    }\n  ]\n}\n\ndata: [DONE]";
 
@@ -468,6 +530,248 @@ mod tests {
             Some(" World".to_string())
         );
 
-        drop(stream_mock);
+        stream_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_chat_completion_llm_error() {
+        let server = MockServer::start();
+        let chat_request = ChatCompletionRequest {
+            model: "unknown-model".to_string(),
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: "Hello".into(),
+                name: None,
+            }],
+            ..Default::default()
+        };
+        let error_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/llm/v1/chat/completions")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::NOT_FOUND.as_u16())
+                .header("Content-Type", "application/json")
+                .body(r#"{"error":{"message":"The model `unknown-model` does not exist","type":"invalid_request_error","code":"model_not_found"}}"#);
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let result = client.create_chat_completion(chat_request).await;
+        error_mock.assert();
+        match result {
+            Err(QstashError::LlmError {
+                status,
+                message,
+                kind,
+                code,
+            }) => {
+                assert_eq!(status, 404);
+                assert_eq!(message, "The model `unknown-model` does not exist");
+                assert_eq!(kind, Some("invalid_request_error".to_string()));
+                assert_eq!(code, Some("model_not_found".to_string()));
+            }
+            _ => panic!("Expected LlmError"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chat_completion_with_timeout_slow_model() {
+        let server = MockServer::start();
+        let chat_request = ChatCompletionRequest {
+            model: "gpt-4".to_string(),
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: "Hello".into(),
+                name: None,
+            }],
+            ..Default::default()
+        };
+        let slow_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/llm/v1/chat/completions")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .delay(Duration::from_secs(2))
+                .json_body_obj(&DirectResponse::default());
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let result = client
+            .create_chat_completion_with_timeout(chat_request, Duration::from_millis(100))
+            .await;
+        slow_mock.assert();
+        match result {
+            Err(QstashError::RequestFailed(err)) => assert!(err.is_timeout()),
+            _ => panic!("Expected a timeout error"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chat_completion_stop_sequences_limit() {
+        let server = MockServer::start();
+        let chat_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/llm/v1/chat/completions")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body_obj(&DirectResponse::default());
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let request_with_stops = |count: usize| ChatCompletionRequest {
+            model: "gpt-4".to_string(),
+            stop: Some((0..count).map(|i| format!("stop{}", i)).collect()),
+            ..Default::default()
+        };
+
+        let result = client.create_chat_completion(request_with_stops(5)).await;
+        assert!(matches!(
+            result,
+            Err(QstashError::InvalidChatCompletionRequest(_))
+        ));
+        chat_mock.assert_hits(0);
+
+        let result = client.create_chat_completion(request_with_stops(4)).await;
+        assert!(matches!(result, Ok(ChatCompletionResponse::Direct(_))));
+        chat_mock.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn test_chat_completion_stream_final_usage() {
+        let server = MockServer::start();
+        let chat_request = ChatCompletionRequest {
+            model: "gpt-4".to_string(),
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: "Hello".into(),
+                name: None,
+            }],
+            stream: Some(true),
+            stream_options: Some(StreamOptions {
+                include_usage: true,
+            }),
+            ..Default::default()
+        };
+        let stream_response = "data: {\"id\": \"chatcmpl-123\", \"object\": \"chat.completion.chunk\", \"created\": 1625097600, \"model\": \"gpt-4\", \"choices\": [{\"delta\": {\"content\": \"Hello\"}, \"finish_reason\": \"stop\", \"index\": 0, \"logprobs\": null}], \"usage\": null}\n\n\
+        data: {\"id\": \"chatcmpl-123\", \"object\": \"chat.completion.chunk\", \"created\": 1625097600, \"model\": \"gpt-4\", \"choices\": [], \"usage\": {\"prompt_tokens\": 9, \"completion_tokens\": 1, \"total_tokens\": 10}}\n\n\
+        data: [DONE]";
+
+        let stream_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/llm/v1/chat/completions")
+                .header("Authorization", "Bearer test_api_key")
+                .json_body_partial(r#"{"stream": true, "stream_options": {"include_usage": true}}"#);
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .body(stream_response);
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let response = client.create_chat_completion(chat_request).await.unwrap();
+        let mut stream = match response {
+            ChatCompletionResponse::Stream(stream_response) => stream_response,
+            _ => panic!("Expected StreamResponse"),
+        };
+        assert!(stream.get_next_stream_message().await.unwrap().is_some());
+        assert!(stream.final_usage().is_none());
+        while stream.get_next_stream_message().await.unwrap().is_some() {}
+
+        assert_eq!(
+            stream.final_usage(),
+            Some(&Usage {
+                completion_tokens: 1,
+                prompt_tokens: 9,
+                total_tokens: 10,
+            })
+        );
+        stream_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_chat_completion_with_cancellation() {
+        let server = MockServer::start();
+        let chat_request = ChatCompletionRequest {
+            model: "gpt-4".to_string(),
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: "Hello".into(),
+                name: None,
+            }],
+            ..Default::default()
+        };
+        server.mock(|when, then| {
+            when.method(POST)
+                .path("/llm/v1/chat/completions")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .delay(Duration::from_secs(2))
+                .json_body_obj(&DirectResponse::default());
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let cancellation_token = tokio_util::sync::CancellationToken::new();
+        let canceller = cancellation_token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            canceller.cancel();
+        });
+        let started = std::time::Instant::now();
+        let result = client
+            .create_chat_completion_with_cancellation(chat_request, &cancellation_token)
+            .await;
+        assert!(matches!(result, Err(QstashError::Cancelled)));
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_chat_completion_rejects_out_of_range_temperature() {
+        let server = MockServer::start();
+        let chat_mock = server.mock(|when, then| {
+            when.method(POST).path("/llm/v1/chat/completions");
+            then.status(StatusCode::OK.as_u16());
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let chat_request = ChatCompletionRequest {
+            model: "gpt-4".to_string(),
+            temperature: Some(2.5),
+            ..Default::default()
+        };
+
+        let result = client.create_chat_completion(chat_request).await;
+
+        match result {
+            Err(QstashError::InvalidChatCompletionRequest(reason)) => {
+                assert!(reason.contains("temperature"), "unexpected reason: {}", reason)
+            }
+            _ => panic!("Expected InvalidChatCompletionRequest"),
+        }
+        chat_mock.assert_hits(0);
     }
 }
@@ -1,5 +1,6 @@
 use crate::errors::QstashError;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::ops::RangeInclusive;
 
 #[derive(Debug, Default, Deserialize, Serialize)]
 #[serde(default)]
@@ -44,11 +45,75 @@ pub struct ChatCompletionRequest {
     /// If set, partial message deltas will be sent. Tokens will be sent as data-only server-sent events as they become available, with the stream terminated by a data: [DONE] message.
     pub stream: Option<bool>,
 
+    /// Options for streaming responses. Only set this when `stream` is true.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<StreamOptions>,
+
     /// What sampling temperature to use, between 0 and 2. Higher values like 0.8 will make the output more random, while lower values like 0.2 will make it more focused and deterministic.
     pub temperature: Option<f64>,
 
     /// An alternative to sampling with temperature, called nucleus sampling, where the model considers the results of the tokens with `top_p` probability mass.
     pub top_p: Option<f64>,
+
+    /// A unique identifier representing your end-user, which can help to monitor and detect abuse.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
+
+/// The API rejects requests with more stop sequences than this.
+const MAX_STOP_SEQUENCES: usize = 4;
+
+/// The ranges the API accepts for the sampling parameters.
+const PENALTY_RANGE: RangeInclusive<f64> = -2.0..=2.0;
+const TEMPERATURE_RANGE: RangeInclusive<f64> = 0.0..=2.0;
+const TOP_P_RANGE: RangeInclusive<f64> = 0.0..=1.0;
+
+impl ChatCompletionRequest {
+    /// Catches requests the API would reject, without a round trip.
+    pub(crate) fn validate(&self) -> Result<(), QstashError> {
+        if let Some(stop) = &self.stop {
+            if stop.len() > MAX_STOP_SEQUENCES {
+                return Err(QstashError::InvalidChatCompletionRequest(format!(
+                    "at most {} stop sequences are allowed, got {}",
+                    MAX_STOP_SEQUENCES,
+                    stop.len()
+                )));
+            }
+        }
+
+        check_range("frequency_penalty", self.frequency_penalty, PENALTY_RANGE)?;
+        check_range("presence_penalty", self.presence_penalty, PENALTY_RANGE)?;
+        check_range("temperature", self.temperature, TEMPERATURE_RANGE)?;
+        check_range("top_p", self.top_p, TOP_P_RANGE)?;
+
+        Ok(())
+    }
+}
+
+fn check_range(
+    name: &str,
+    value: Option<f64>,
+    range: RangeInclusive<f64>,
+) -> Result<(), QstashError> {
+    match value {
+        Some(value) if !range.contains(&value) => {
+            Err(QstashError::InvalidChatCompletionRequest(format!(
+                "{} must be between {} and {}, got {}",
+                name,
+                range.start(),
+                range.end(),
+                value
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct StreamOptions {
+    /// If set, an additional chunk with the token usage statistics for the entire request is streamed before the data: [DONE] message.
+    pub include_usage: bool,
 }
 
 #[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
@@ -58,12 +123,72 @@ pub struct Message {
     pub role: String,
 
     /// The content of the message.
-    pub content: String,
+    pub content: MessageContent,
 
     /// An optional name for the participant. Provides the model information to differentiate between participants of the same role.
     pub name: Option<String>,
 }
 
+/// The content of a message: plain text, or a list of parts for models that also accept images.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+impl MessageContent {
+    /// The text of a plain text message, `None` for a message made of parts.
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            MessageContent::Text(text) => Some(text),
+            MessageContent::Parts(_) => None,
+        }
+    }
+}
+
+impl Default for MessageContent {
+    fn default() -> Self {
+        MessageContent::Text(String::new())
+    }
+}
+
+impl From<String> for MessageContent {
+    fn from(text: String) -> Self {
+        MessageContent::Text(text)
+    }
+}
+
+impl From<&str> for MessageContent {
+    fn from(text: &str) -> Self {
+        MessageContent::Text(text.to_string())
+    }
+}
+
+impl From<Vec<ContentPart>> for MessageContent {
+    fn from(parts: Vec<ContentPart>) -> Self {
+        MessageContent::Parts(parts)
+    }
+}
+
+/// A part of a multimodal message.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    Text { text: String },
+    ImageUrl { image_url: ImageUrl },
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+pub struct ImageUrl {
+    /// The URL of the image, or the image itself as a base64 encoded data URL.
+    pub url: String,
+
+    /// How closely the model looks at the image. One of `auto`, `low`, or `high`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum FormatType {
@@ -103,6 +228,15 @@ pub struct DirectResponse {
     pub usage: Usage,
 }
 
+impl DirectResponse {
+    /// Whether both responses were generated by the same backend configuration, according to
+    /// their `system_fingerprint`. Together with a fixed `seed` this tells whether differing
+    /// outputs may come from a backend change. Returns `false` if either fingerprint is missing.
+    pub fn same_backend(&self, other: &DirectResponse) -> bool {
+        !self.system_fingerprint.is_empty() && self.system_fingerprint == other.system_fingerprint
+    }
+}
+
 #[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
 pub struct Choice {
     // A chat completion message generated by the model
@@ -122,6 +256,7 @@ pub struct Choice {
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct LogProbs {
     // A list of message content tokens with log probability information
+    #[serde(default, deserialize_with = "deserialize_null_default")]
     pub content: Vec<TokenInfo>,
 }
 
@@ -134,6 +269,7 @@ pub struct TokenInfo {
     // A list of integers representing the UTF-8 bytes representation of the token
     pub bytes: Option<Vec<i32>>,
     // List of the most likely tokens and their log probability
+    #[serde(default, deserialize_with = "deserialize_null_default")]
     pub top_logprobs: Vec<TopLogProb>,
 }
 
@@ -147,7 +283,7 @@ pub struct TopLogProb {
     pub bytes: Option<Vec<i32>>,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(default)]
 pub struct Usage {
     // Number of tokens in the generated completion
@@ -197,15 +333,58 @@ pub struct Delta {
     pub content: Option<String>,
 }
 
+// Some providers send `null` instead of an empty list.
+fn deserialize_null_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// OpenAI-style error body returned by the LLM endpoint on failures.
+#[derive(Debug, Deserialize)]
+pub(crate) struct LlmErrorResponse {
+    pub error: LlmErrorDetails,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct LlmErrorDetails {
+    pub message: String,
+    #[serde(rename = "type", default)]
+    pub kind: Option<String>,
+    // Some providers send the code as a number, others as a string.
+    #[serde(default)]
+    pub code: Option<serde_json::Value>,
+}
+
+impl LlmErrorResponse {
+    /// Converts the body of a response that failed with `status` into `QstashError::LlmError`.
+    pub(crate) fn into_error(self, status: u16) -> QstashError {
+        let code = self.error.code.and_then(|code| match code {
+            serde_json::Value::Null => None,
+            serde_json::Value::String(code) => Some(code),
+            code => Some(code.to_string()),
+        });
+        QstashError::LlmError {
+            status,
+            message: self.error.message,
+            kind: self.error.kind,
+            code,
+        }
+    }
+}
+
 enum ChunkType {
     Message(Vec<u8>),
     Done(),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct StreamResponse {
     response: Option<reqwest::Response>, // Use RefCell for interior mutability
     buffer: Vec<u8>,
+    final_usage: Option<Usage>,
 }
 
 impl StreamResponse {
@@ -213,28 +392,52 @@ impl StreamResponse {
         Self {
             response: Some(response),
             buffer: Vec::new(),
+            final_usage: None,
         }
     }
 
-    pub fn default() -> Self {
-        Self {
-            response: None,
-            buffer: Vec::new(),
-        }
+    /// Token usage for the entire request, sent in the last chunk when the request sets
+    /// `stream_options.include_usage`. Available once that chunk has been read.
+    pub fn final_usage(&self) -> Option<&Usage> {
+        self.final_usage.as_ref()
     }
 
     pub async fn get_next_stream_message(&mut self) -> Result<Option<StreamMessage>, QstashError> {
         let chunk = self.poll_chunk().await?;
         match chunk {
             ChunkType::Message(data) => {
-                let message =
+                let message: StreamMessage =
                     serde_json::from_slice(&data).map_err(QstashError::ResponseStreamParseError)?;
+                if let Some(usage) = &message.usage {
+                    self.final_usage = Some(usage.clone());
+                }
                 Ok(Some(message))
             }
             ChunkType::Done() => Ok(None),
         }
     }
 
+    /// Reads the rest of the stream, concatenates the content deltas of the first choice and
+    /// parses them as JSON. Meant for requests with a `json_object` response format.
+    ///
+    /// Fails with `QstashError::ResponseStreamParseError` if the stream ends before the JSON
+    /// value is complete.
+    pub async fn collect_json(mut self) -> Result<serde_json::Value, QstashError> {
+        let mut content = String::new();
+        while let Some(message) = self.get_next_stream_message().await? {
+            if let Some(delta) = message
+                .choices
+                .iter()
+                .find(|choice| choice.index == 0)
+                .and_then(|choice| choice.delta.content.as_deref())
+            {
+                content.push_str(delta);
+            }
+        }
+
+        serde_json::from_str(&content).map_err(QstashError::ResponseStreamParseError)
+    }
+
     async fn poll_chunk(&mut self) -> Result<ChunkType, QstashError> {
         loop {
             // Now we can mutably borrow self for extract_next_message
@@ -291,7 +494,71 @@ impl StreamResponse {
 
 #[cfg(test)]
 mod tests {
-    use crate::llm_types::StreamResponse;
+    use crate::errors::QstashError;
+    use crate::llm_types::{
+        ChatCompletionRequest, ContentPart, DirectResponse, ImageUrl, Message, StreamMessage,
+        StreamResponse,
+    };
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_chat_completion_request_user_serialization() {
+        let request = ChatCompletionRequest {
+            model: "gpt-4".to_string(),
+            user: Some("user-123".to_string()),
+            ..Default::default()
+        };
+        let serialized = serde_json::to_value(&request).unwrap();
+        assert_eq!(serialized["user"], "user-123");
+
+        let request = ChatCompletionRequest {
+            model: "gpt-4".to_string(),
+            ..Default::default()
+        };
+        let serialized = serde_json::to_value(&request).unwrap();
+        assert!(serialized.get("user").is_none());
+    }
+
+    #[test]
+    fn test_message_content_serialization() {
+        let message = Message {
+            role: "user".to_string(),
+            content: vec![
+                ContentPart::Text {
+                    text: "What is in this image?".to_string(),
+                },
+                ContentPart::ImageUrl {
+                    image_url: ImageUrl {
+                        url: "https://example.com/cat.png".to_string(),
+                        detail: None,
+                    },
+                },
+            ]
+            .into(),
+            name: None,
+        };
+        let serialized = serde_json::to_value(&message).unwrap();
+        assert_eq!(
+            serialized["content"],
+            serde_json::json!([
+                {"type": "text", "text": "What is in this image?"},
+                {"type": "image_url", "image_url": {"url": "https://example.com/cat.png"}}
+            ])
+        );
+        let deserialized: Message = serde_json::from_value(serialized).unwrap();
+        assert_eq!(deserialized, message);
+
+        let message = Message {
+            role: "user".to_string(),
+            content: "Hello".into(),
+            name: None,
+        };
+        let serialized = serde_json::to_value(&message).unwrap();
+        assert_eq!(serialized["content"], "Hello");
+        let deserialized: Message = serde_json::from_value(serialized).unwrap();
+        assert_eq!(deserialized.content.as_text(), Some("Hello"));
+    }
+
     #[test]
     fn test_extract_next_message_logic() {
         let mut stream_response = StreamResponse::default();
@@ -312,4 +579,157 @@ mod tests {
             Some(b"[DONE]".to_vec())
         );
     }
+
+    #[tokio::test]
+    async fn test_collect_json_assembles_chunks() {
+        let chunk = |content: &str| {
+            format!(
+                "data: {}\n\n",
+                json!({
+                    "id": "chatcmpl-123",
+                    "object": "chat.completion.chunk",
+                    "choices": [{"delta": {"content": content}, "finish_reason": null, "index": 0}]
+                })
+            )
+        };
+        let stream = |contents: &[&str]| {
+            let mut stream_response = StreamResponse::default();
+            for content in contents {
+                stream_response
+                    .buffer
+                    .extend_from_slice(chunk(content).as_bytes());
+            }
+            stream_response.buffer.extend_from_slice(b"data: [DONE]");
+            stream_response
+        };
+
+        let value = stream(&["{\"city\": ", "\"Par", "is\", \"population\"", ": 2102650}"])
+            .collect_json()
+            .await
+            .unwrap();
+        assert_eq!(value, json!({"city": "Paris", "population": 2102650}));
+
+        let result = stream(&["{\"city\": ", "\"Par"]).collect_json().await;
+        assert!(matches!(
+            result,
+            Err(crate::errors::QstashError::ResponseStreamParseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_stream_message_metadata_fields() {
+        let chunk = br#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1625097600,"model":"gpt-4","choices":[{"delta":{"content":"Hello"},"finish_reason":null,"index":0,"logprobs":null}]}"#;
+
+        let message: StreamMessage = serde_json::from_slice(chunk).unwrap();
+
+        assert_eq!(message.id, "chatcmpl-123");
+        assert_eq!(message.object, "chat.completion.chunk");
+        assert_eq!(message.created, 1625097600);
+        assert_eq!(message.model, "gpt-4");
+        assert_eq!(message.choices[0].delta.content.as_deref(), Some("Hello"));
+    }
+
+    #[test]
+    fn test_logit_bias_round_trip() {
+        let request = ChatCompletionRequest {
+            model: "gpt-4".to_string(),
+            logit_bias: Some(HashMap::from([
+                ("50256".to_string(), -100.0),
+                ("1234".to_string(), 5.5),
+            ])),
+            ..Default::default()
+        };
+
+        let serialized = serde_json::to_value(&request).unwrap();
+        assert_eq!(serialized["logit_bias"], json!({"50256": -100.0, "1234": 5.5}));
+
+        let deserialized: ChatCompletionRequest = serde_json::from_value(serialized).unwrap();
+        assert_eq!(deserialized.logit_bias, request.logit_bias);
+    }
+
+    #[test]
+    fn test_direct_response_tolerates_null_logprobs() {
+        let response: DirectResponse = serde_json::from_value(json!({
+            "id": "chatcmpl-123",
+            "choices": [
+                {
+                    "message": {"role": "assistant", "content": "Hi"},
+                    "finishReason": "stop",
+                    "index": 0,
+                    "logprobs": null
+                },
+                {
+                    "message": {"role": "assistant", "content": "Hello"},
+                    "index": 1,
+                    "logprobs": {
+                        "content": [
+                            {"token": "Hello", "logprob": -0.1, "bytes": null, "top_logprobs": null}
+                        ]
+                    }
+                },
+                {
+                    "message": {"role": "assistant", "content": ""},
+                    "index": 2,
+                    "logprobs": {"content": null}
+                }
+            ]
+        }))
+        .unwrap();
+
+        assert!(response.choices[0].logprobs.is_none());
+        let logprobs = response.choices[1].logprobs.as_ref().unwrap();
+        assert!(logprobs.content[0].top_logprobs.is_empty());
+        assert!(response.choices[2].logprobs.as_ref().unwrap().content.is_empty());
+    }
+
+    #[test]
+    fn test_direct_response_same_backend() {
+        let response = |fingerprint: &str| DirectResponse {
+            system_fingerprint: fingerprint.to_string(),
+            ..Default::default()
+        };
+
+        assert!(response("fp_44709d6fcb").same_backend(&response("fp_44709d6fcb")));
+        assert!(!response("fp_44709d6fcb").same_backend(&response("fp_3bc1b5746c")));
+        assert!(!response("").same_backend(&response("")));
+    }
+
+    #[test]
+    fn test_chat_completion_request_sampling_ranges() {
+        type Setter = fn(&mut ChatCompletionRequest, f64);
+        let parameters: [(Setter, f64, f64); 4] = [
+            (
+                |request, value| request.frequency_penalty = Some(value),
+                -2.0,
+                2.0,
+            ),
+            (
+                |request, value| request.presence_penalty = Some(value),
+                -2.0,
+                2.0,
+            ),
+            (|request, value| request.temperature = Some(value), 0.0, 2.0),
+            (|request, value| request.top_p = Some(value), 0.0, 1.0),
+        ];
+
+        for (set, min, max) in parameters {
+            for value in [min, max] {
+                let mut request = ChatCompletionRequest::default();
+                set(&mut request, value);
+                assert!(request.validate().is_ok(), "{} should be accepted", value);
+            }
+            for value in [min - 0.1, max + 0.1, f64::NAN] {
+                let mut request = ChatCompletionRequest::default();
+                set(&mut request, value);
+                assert!(
+                    matches!(
+                        request.validate(),
+                        Err(QstashError::InvalidChatCompletionRequest(_))
+                    ),
+                    "{} should be rejected",
+                    value
+                );
+            }
+        }
+    }
 }
@@ -1,11 +1,17 @@
-use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
-use serde::de::{self};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+use reqwest::Url;
+use serde::{de, ser};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
+use std::fmt;
+
+use crate::errors::QstashError;
 
 #[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "camelCase", default)]
 pub struct Message {
+    #[serde(deserialize_with = "deserialize_id")]
     pub message_id: String,
     pub topic_name: String,
     pub url: String,
@@ -13,48 +19,516 @@ pub struct Message {
     pub header: HashMap<String, Vec<String>>,
     pub body: String,
     pub created_at: i64,
+    /// Whether QStash cut the stored body short. When set, `body` is incomplete and the full
+    /// body has to be fetched from the producer. Defaults to `false` when the response doesn't
+    /// include it.
+    #[serde(default)]
+    pub body_truncated: bool,
+}
+
+impl Message {
+    /// Returns the headers QStash forwards to the destination.
+    ///
+    /// `Upstash-Forward-<name>` headers are returned under `<name>`, other `Upstash-*` headers
+    /// only configure QStash and are left out. Prefixes are matched case-insensitively.
+    pub fn forwarded_headers(&self) -> HashMap<String, Vec<String>> {
+        const FORWARD_PREFIX: &str = "upstash-forward-";
+        const CONTROL_PREFIX: &str = "upstash-";
+
+        let mut forwarded: HashMap<String, Vec<String>> = HashMap::new();
+        for (name, values) in &self.header {
+            let lowercase = name.to_ascii_lowercase();
+            let name = if lowercase.starts_with(FORWARD_PREFIX) {
+                &name[FORWARD_PREFIX.len()..]
+            } else if lowercase.starts_with(CONTROL_PREFIX) {
+                continue;
+            } else {
+                name.as_str()
+            };
+            forwarded
+                .entry(name.to_string())
+                .or_default()
+                .extend(values.iter().cloned());
+        }
+        forwarded
+    }
+}
+
+/// The body QStash sends to a message's callback URL once a delivery attempt completes.
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CallbackPayload {
+    /// The HTTP status the destination responded with.
+    pub status: u16,
+    /// The headers of the destination's response.
+    pub header: HashMap<String, Vec<String>>,
+    /// The base64 encoded body of the destination's response.
+    pub body: String,
+    /// How many times delivery has been retried so far.
+    pub retried: u32,
+    pub max_retries: u32,
+    #[serde(deserialize_with = "deserialize_id")]
+    pub source_message_id: String,
+    pub topic_name: Option<String>,
+    pub endpoint_name: Option<String>,
+    /// The destination URL of the source message.
+    pub url: String,
+    pub method: String,
+    /// The headers of the source message.
+    pub source_header: HashMap<String, Vec<String>>,
+    /// The base64 encoded body of the source message.
+    pub source_body: String,
+    pub schedule_id: Option<String>,
+    #[serde(rename = "callerIP")]
+    pub caller_ip: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct MessageResponse {
+    #[serde(deserialize_with = "deserialize_id")]
     pub message_id: String,
     #[serde(default)]
     pub url: Option<String>,
     #[serde(default)]
     pub deduplicated: Option<bool>,
+    /// The id of the schedule the message originated from, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule_id: Option<String>,
 }
 
+/// The response to a published message, or to one entry of a batch.
+///
+/// QStash answers with a single object when the destination is a URL and with an array of
+/// objects, one per endpoint, when it is a URL group. The enum is untagged, so it serializes back
+/// to the same shape it was read from.
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 #[serde(untagged)]
 pub enum MessageResponseResult {
+    /// A bare `{"messageId": ...}` object, returned for URL destinations.
     URLResponse(MessageResponse),
+    /// A `[{"messageId": ...}, ...]` array, returned for URL group destinations.
     URLGroupResponse(Vec<MessageResponse>),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl MessageResponseResult {
+    /// Flattens the result into one response per destination URL.
+    pub fn into_responses(self) -> Vec<MessageResponse> {
+        match self {
+            MessageResponseResult::URLResponse(response) => vec![response],
+            MessageResponseResult::URLGroupResponse(responses) => responses,
+        }
+    }
+
+    // Fills in the schedule id on every response that doesn't already carry one.
+    pub(crate) fn set_schedule_id(&mut self, schedule_id: &str) {
+        let responses = match self {
+            MessageResponseResult::URLResponse(response) => std::slice::from_mut(response),
+            MessageResponseResult::URLGroupResponse(responses) => responses.as_mut_slice(),
+        };
+        for response in responses.iter_mut().filter(|r| r.schedule_id.is_none()) {
+            response.schedule_id = Some(schedule_id.to_string());
+        }
+    }
+}
+
+/// Response of the cancel messages endpoint.
+#[derive(Debug, Deserialize)]
+pub(crate) struct CancelMessagesResponse {
+    pub cancelled: usize,
+}
+
+/// Body of a message to publish.
+///
+/// Each variant comes with a default `Content-Type` which is sent only when the
+/// headers passed alongside the body don't already set one.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PublishBody {
+    /// Serialized as JSON and sent as `application/json`.
+    Json(serde_json::Value),
+    /// Sent as `text/plain; charset=utf-8`.
+    Text(String),
+    /// Sent as-is, as `application/octet-stream`.
+    Bytes(Vec<u8>),
+}
+
+impl PublishBody {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            PublishBody::Json(_) => "application/json",
+            PublishBody::Text(_) => "text/plain; charset=utf-8",
+            PublishBody::Bytes(_) => "application/octet-stream",
+        }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        match self {
+            PublishBody::Json(value) => value.to_string().into_bytes(),
+            PublishBody::Text(text) => text.into_bytes(),
+            PublishBody::Bytes(bytes) => bytes,
+        }
+    }
+}
+
+impl From<serde_json::Value> for PublishBody {
+    fn from(value: serde_json::Value) -> Self {
+        PublishBody::Json(value)
+    }
+}
+
+impl From<String> for PublishBody {
+    fn from(text: String) -> Self {
+        PublishBody::Text(text)
+    }
+}
+
+impl From<&str> for PublishBody {
+    fn from(text: &str) -> Self {
+        PublishBody::Text(text.to_string())
+    }
+}
+
+impl From<Vec<u8>> for PublishBody {
+    fn from(bytes: Vec<u8>) -> Self {
+        PublishBody::Bytes(bytes)
+    }
+}
+
+impl From<&[u8]> for PublishBody {
+    fn from(bytes: &[u8]) -> Self {
+        PublishBody::Bytes(bytes.to_vec())
+    }
+}
+
+/// Destination URL of a message, built from components so that every path segment and query
+/// pair is percent-encoded.
+///
+/// Use `as_str` to pass the result to `publish_message` or `enqueue_message`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Destination {
+    url: Url,
+}
+
+impl Destination {
+    pub fn url(base: &str) -> Result<Self, QstashError> {
+        let url = Url::parse(base).map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?;
+        if url.cannot_be_a_base() {
+            return Err(QstashError::InvalidRequestUrl(format!(
+                "{} can't be used as a destination base",
+                base
+            )));
+        }
+        Ok(Destination { url })
+    }
+
+    /// Appends `path` to the destination. Segments are split on `/` and each one is encoded.
+    pub fn with_path(mut self, path: &str) -> Self {
+        if let Ok(mut segments) = self.url.path_segments_mut() {
+            segments
+                .pop_if_empty()
+                .extend(path.split('/').filter(|segment| !segment.is_empty()));
+        }
+        self
+    }
+
+    pub fn with_query(mut self, key: &str, value: &str) -> Self {
+        self.url.query_pairs_mut().append_pair(key, value);
+        self
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.url.as_str()
+    }
+}
+
+impl fmt::Display for Destination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Deserialize)]
 pub struct BatchEntry {
     pub destination: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub queue: Option<String>,
-    #[serde(
-        serialize_with = "serialize_headers",
-        deserialize_with = "deserialize_headers"
-    )]
+    /// Batch entries carry their headers as a flat JSON object, so the values of a repeated
+    /// header are sent joined with `", "`. `Set-Cookie` values can't be joined that way, and a
+    /// repeated `Set-Cookie` fails the batch with `QstashError::InvalidHeader`; publish such
+    /// messages one by one instead.
+    #[serde(deserialize_with = "deserialize_headers")]
     pub headers: HeaderMap,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub body: Option<String>,
+    /// Base64 encoded body, for payloads that aren't valid UTF-8. Sent instead of `body` when
+    /// set, see `body_bytes`.
+    #[serde(rename = "bodyBase64")]
+    pub body_base64: Option<String>,
+    /// URL called when delivery of this entry fails, sent as its `Upstash-Failure-Callback`
+    /// header. Takes precedence over a failure callback set in `headers`.
+    #[serde(skip)]
+    pub failure_callback: Option<String>,
+}
+
+impl BatchEntry {
+    /// An entry publishing to `destination`, without a body.
+    pub fn new(destination: &str) -> Self {
+        BatchEntry {
+            destination: destination.to_string(),
+            queue: None,
+            headers: HeaderMap::new(),
+            body: None,
+            body_base64: None,
+            failure_callback: None,
+        }
+    }
+
+    /// Enqueues the entry to `queue_name` instead of publishing it directly.
+    pub fn queue(mut self, queue_name: &str) -> Self {
+        self.queue = Some(queue_name.to_string());
+        self
+    }
+
+    pub fn body(mut self, body: &str) -> Self {
+        self.body = Some(body.to_string());
+        self.body_base64 = None;
+        self
+    }
+
+    /// Sets a binary body, sent base64 encoded so it survives the JSON batch request.
+    pub fn body_bytes(mut self, body: &[u8]) -> Self {
+        self.body_base64 = Some(STANDARD.encode(body));
+        self.body = None;
+        self
+    }
+
+    /// Sets the `Content-Type` header the body is delivered with.
+    pub fn content_type(mut self, content_type: &str) -> Result<Self, QstashError> {
+        let value = HeaderValue::from_str(content_type)
+            .map_err(|e| QstashError::InvalidHeader(e.to_string()))?;
+        self.headers.insert(CONTENT_TYPE, value);
+        Ok(self)
+    }
+}
+
+// BatchEntry is serialized through this view so `failure_callback` ends up in the headers.
+#[derive(Serialize)]
+struct SerializedBatchEntry<'a> {
+    destination: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    queue: Option<&'a str>,
+    #[serde(serialize_with = "serialize_headers")]
+    headers: HeaderMap,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<&'a str>,
+    #[serde(rename = "bodyBase64", skip_serializing_if = "Option::is_none")]
+    body_base64: Option<&'a str>,
+}
+
+impl Serialize for BatchEntry {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut headers = self.headers.clone();
+        if let Some(failure_callback) = &self.failure_callback {
+            let value = HeaderValue::from_str(failure_callback).map_err(ser::Error::custom)?;
+            headers.insert("Upstash-Failure-Callback", value);
+        }
+
+        SerializedBatchEntry {
+            destination: &self.destination,
+            queue: self.queue.as_deref(),
+            headers,
+            body: self.body.as_deref().filter(|_| self.body_base64.is_none()),
+            body_base64: self.body_base64.as_deref(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Pulls the top-level `body` string out of a message JSON document as it streams in,
+/// unescaping it on the fly so the document never has to be held in memory.
+#[derive(Debug, Default)]
+pub(crate) struct MessageBodyExtractor {
+    state: ExtractorState,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    expecting_key: bool,
+    key: Option<Vec<u8>>,
+    value_is_body: bool,
+    escape: Option<Vec<u8>>,
+    high_surrogate: Option<u32>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+enum ExtractorState {
+    #[default]
+    Scanning,
+    Body,
+    Done,
+}
+
+impl MessageBodyExtractor {
+    pub(crate) fn is_done(&self) -> bool {
+        self.state == ExtractorState::Done
+    }
+
+    /// Feeds the next chunk of the document and returns the body bytes it completed.
+    pub(crate) fn feed(&mut self, chunk: &[u8]) -> Result<Vec<u8>, serde_json::Error> {
+        let mut body = Vec::new();
+        for &byte in chunk {
+            match self.state {
+                ExtractorState::Scanning => self.scan(byte),
+                ExtractorState::Body => self.read_body(byte, &mut body)?,
+                ExtractorState::Done => break,
+            }
+        }
+        Ok(body)
+    }
+
+    /// Checks that the document didn't end in the middle of the body.
+    pub(crate) fn finish(&self) -> Result<(), serde_json::Error> {
+        match self.state {
+            ExtractorState::Body => Err(de::Error::custom("message body is truncated")),
+            _ => Ok(()),
+        }
+    }
+
+    fn scan(&mut self, byte: u8) {
+        if self.in_string {
+            if self.escaped {
+                self.escaped = false;
+            } else if byte == b'\\' {
+                self.escaped = true;
+            } else if byte == b'"' {
+                self.in_string = false;
+                if let Some(key) = self.key.take() {
+                    self.value_is_body = key == b"body";
+                }
+                return;
+            }
+            if let Some(key) = self.key.as_mut() {
+                key.push(byte);
+            }
+            return;
+        }
+
+        match byte {
+            b'"' if self.depth == 1 && self.value_is_body => self.state = ExtractorState::Body,
+            b'"' => {
+                self.in_string = true;
+                if self.depth == 1 && self.expecting_key {
+                    self.expecting_key = false;
+                    self.key = Some(Vec::new());
+                }
+            }
+            b'{' | b'[' => {
+                self.depth += 1;
+                self.expecting_key = self.depth == 1;
+                self.value_is_body = false;
+            }
+            b'}' | b']' => self.depth = self.depth.saturating_sub(1),
+            b',' => self.expecting_key = self.depth == 1,
+            b':' | b' ' | b'\t' | b'\n' | b'\r' => {}
+            _ => self.value_is_body = false,
+        }
+    }
+
+    fn read_body(&mut self, byte: u8, body: &mut Vec<u8>) -> Result<(), serde_json::Error> {
+        let Some(escape) = self.escape.as_mut() else {
+            if self.high_surrogate.is_some() && byte != b'\\' {
+                return Err(de::Error::custom("unpaired surrogate in message body"));
+            }
+            match byte {
+                b'\\' => self.escape = Some(Vec::new()),
+                b'"' => self.state = ExtractorState::Done,
+                _ => body.push(byte),
+            }
+            return Ok(());
+        };
+
+        escape.push(byte);
+        let unescaped = match escape[0] {
+            b'u' if escape.len() < 5 => return Ok(()),
+            b'u' => {
+                let hex = std::str::from_utf8(&escape[1..]).unwrap_or_default();
+                let code = u32::from_str_radix(hex, 16)
+                    .map_err(|_| de::Error::custom("invalid unicode escape in message body"))?;
+                self.escape = None;
+                match (self.high_surrogate.take(), code) {
+                    (None, 0xD800..=0xDBFF) => {
+                        self.high_surrogate = Some(code);
+                        return Ok(());
+                    }
+                    (Some(high), 0xDC00..=0xDFFF) => {
+                        char::from_u32(0x10000 + ((high - 0xD800) << 10) + (code - 0xDC00))
+                    }
+                    (None, code) => char::from_u32(code),
+                    (Some(_), _) => None,
+                }
+                .ok_or_else(|| de::Error::custom("unpaired surrogate in message body"))?
+            }
+            _ if self.high_surrogate.is_some() => {
+                return Err(de::Error::custom("unpaired surrogate in message body"))
+            }
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            _ => return Err(de::Error::custom("invalid escape in message body")),
+        };
+        self.escape = None;
+        body.extend_from_slice(unescaped.encode_utf8(&mut [0; 4]).as_bytes());
+        Ok(())
+    }
 }
 
-// Custom serializer for HeaderMap
+/// Deserializes an id that QStash may send either as a string or as a number.
+pub(crate) fn deserialize_id<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Id {
+        String(String),
+        Unsigned(u64),
+        Signed(i64),
+    }
+
+    Ok(match Id::deserialize(deserializer)? {
+        Id::String(id) => id,
+        Id::Unsigned(id) => id.to_string(),
+        Id::Signed(id) => id.to_string(),
+    })
+}
+
+// Custom serializer for HeaderMap. Batch entries carry headers as a flat object, so the values
+// of a repeated header are joined into one comma separated value like HTTP list headers. That
+// would corrupt `Set-Cookie`, whose values may contain commas, so a repeated one is an error.
 fn serialize_headers<S>(headers: &HeaderMap, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
-    let headers_map: std::collections::HashMap<String, String> = headers
-        .iter()
-        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or_default().to_string()))
-        .collect();
+    let mut headers_map = HashMap::new();
+    for name in headers.keys() {
+        let values: Vec<&str> = headers
+            .get_all(name)
+            .iter()
+            .map(|value| value.to_str().unwrap_or_default())
+            .collect();
+        if values.len() > 1 && name.as_str().ends_with("set-cookie") {
+            return Err(ser::Error::custom(format!(
+                "{} can't be sent more than once in a batch entry",
+                name
+            )));
+        }
+        headers_map.insert(name.to_string(), values.join(", "));
+    }
     headers_map.serialize(serializer)
 }
 
@@ -130,6 +604,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_publish_body_variants() {
+        let json_body = PublishBody::from(serde_json::json!({"key": "value"}));
+        assert_eq!(json_body.content_type(), "application/json");
+        assert_eq!(json_body.into_bytes(), b"{\"key\":\"value\"}".to_vec());
+
+        let text_body = PublishBody::from("Hello");
+        assert_eq!(text_body.content_type(), "text/plain; charset=utf-8");
+        assert_eq!(text_body.into_bytes(), b"Hello".to_vec());
+
+        let bytes_body = PublishBody::from(vec![0x00, 0xFF]);
+        assert_eq!(bytes_body.content_type(), "application/octet-stream");
+        assert_eq!(bytes_body.into_bytes(), vec![0x00, 0xFF]);
+    }
+
     #[test]
     fn test_batch_entry_serialization() {
         let mut headers = HeaderMap::new();
@@ -141,6 +630,8 @@ mod tests {
             queue: Some("some_queue".to_string()),
             headers,
             body: Some("This is a body".to_string()),
+            body_base64: None,
+            failure_callback: None,
         };
 
         let serialized =
@@ -161,4 +652,262 @@ mod tests {
             assert_eq!(value, deserialized_value);
         }
     }
+
+    #[test]
+    fn test_destination_encodes_path_and_query() {
+        let destination = Destination::url("https://example.com/api/")
+            .unwrap()
+            .with_path("users/john doe/ä")
+            .with_query("filter", "a&b=c")
+            .with_query("page", "2");
+
+        assert_eq!(
+            destination.as_str(),
+            "https://example.com/api/users/john%20doe/%C3%A4?filter=a%26b%3Dc&page=2"
+        );
+        assert_eq!(destination.to_string(), destination.as_str());
+    }
+
+    #[test]
+    fn test_destination_rejects_invalid_base() {
+        assert!(matches!(
+            Destination::url("not a url"),
+            Err(QstashError::InvalidRequestUrl(_))
+        ));
+        assert!(matches!(
+            Destination::url("mailto:someone@example.com"),
+            Err(QstashError::InvalidRequestUrl(_))
+        ));
+    }
+
+    #[test]
+    fn test_batch_entry_failure_callback_serialization() {
+        let batch_entries = vec![
+            BatchEntry {
+                destination: "https://example.com/first".to_string(),
+                queue: None,
+                headers: HeaderMap::new(),
+                body: None,
+                body_base64: None,
+                failure_callback: Some("https://example.com/first-failed".to_string()),
+            },
+            BatchEntry {
+                destination: "https://example.com/second".to_string(),
+                queue: None,
+                headers: HeaderMap::new(),
+                body: None,
+                body_base64: None,
+                failure_callback: None,
+            },
+        ];
+
+        let serialized = serde_json::to_value(&batch_entries).unwrap();
+
+        assert_eq!(
+            serialized[0]["headers"]["upstash-failure-callback"],
+            "https://example.com/first-failed"
+        );
+        assert!(serialized[1]["headers"]
+            .get("upstash-failure-callback")
+            .is_none());
+    }
+
+    #[test]
+    fn test_batch_entry_content_type() {
+        let entry = BatchEntry::new("https://example.com/publish")
+            .queue("queue1")
+            .body(r#"{"key":"value"}"#)
+            .content_type("application/json")
+            .unwrap();
+
+        let serialized = serde_json::to_value(&entry).unwrap();
+
+        assert_eq!(serialized["headers"]["content-type"], "application/json");
+        assert_eq!(serialized["queue"], "queue1");
+        assert_eq!(serialized["body"], r#"{"key":"value"}"#);
+        assert!(BatchEntry::new("https://example.com/publish")
+            .content_type("invalid\n")
+            .is_err());
+    }
+
+    #[test]
+    fn test_message_body_extractor_byte_by_byte() {
+        let message = Message {
+            message_id: "msg123".to_string(),
+            url: "https://example.com/publish".to_string(),
+            header: HashMap::from([("body".to_string(), vec!["not the body".to_string()])]),
+            body: "{\"key\":\"line\\n\\u00e9 \u{1F600}\"}\n\ttab".to_string(),
+            created_at: 1625097600,
+            ..Default::default()
+        };
+        let document = serde_json::to_vec(&serde_json::json!({
+            "header": message.header,
+            "messageId": message.message_id,
+            "body": message.body,
+            "url": message.url,
+        }))
+        .unwrap();
+
+        let mut extractor = MessageBodyExtractor::default();
+        let mut body = Vec::new();
+        for byte in &document {
+            body.extend(extractor.feed(std::slice::from_ref(byte)).unwrap());
+        }
+        extractor.finish().unwrap();
+
+        assert!(extractor.is_done());
+        assert_eq!(String::from_utf8(body).unwrap(), message.body);
+    }
+
+    #[test]
+    fn test_message_body_extractor_unescapes_unicode() {
+        let mut extractor = MessageBodyExtractor::default();
+        let body = extractor
+            .feed(br#"{"messageId":"msg1","body":"caf\u00e9 \ud83d\ude00 \"quoted\""}"#)
+            .unwrap();
+
+        assert_eq!(String::from_utf8(body).unwrap(), "café 😀 \"quoted\"");
+    }
+
+    #[test]
+    fn test_message_body_extractor_truncated_body() {
+        let mut extractor = MessageBodyExtractor::default();
+        extractor.feed(br#"{"body":"unfinished"#).unwrap();
+
+        assert!(extractor.finish().is_err());
+    }
+
+    #[test]
+    fn test_message_ids_as_string_or_number() {
+        let from_string: MessageResponse =
+            serde_json::from_str(r#"{"messageId": "msd_1234"}"#).unwrap();
+        assert_eq!(from_string.message_id, "msd_1234");
+
+        let from_number: MessageResponse = serde_json::from_str(r#"{"messageId": 1234}"#).unwrap();
+        assert_eq!(from_number.message_id, "1234");
+
+        let message: Message = serde_json::from_str(r#"{"messageId": -42}"#).unwrap();
+        assert_eq!(message.message_id, "-42");
+
+        assert!(serde_json::from_str::<MessageResponse>(r#"{"messageId": true}"#).is_err());
+    }
+
+    #[test]
+    fn test_message_body_truncated() {
+        let truncated: Message = serde_json::from_str(
+            r#"{"messageId": "msg1", "body": "partial", "bodyTruncated": true}"#,
+        )
+        .unwrap();
+        assert!(truncated.body_truncated);
+
+        let complete: Message =
+            serde_json::from_str(r#"{"messageId": "msg1", "body": "full"}"#).unwrap();
+        assert!(!complete.body_truncated);
+    }
+
+    #[test]
+    fn test_message_forwarded_headers() {
+        let message: Message = serde_json::from_str(
+            r#"{
+                "messageId": "msg1",
+                "header": {
+                    "Upstash-Forward-X-Request-Id": ["req-1"],
+                    "upstash-forward-authorization": ["Bearer token"],
+                    "Upstash-Retries": ["3"],
+                    "Upstash-Delay": ["10s"],
+                    "Content-Type": ["application/json"]
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let forwarded = message.forwarded_headers();
+
+        assert_eq!(forwarded.len(), 3);
+        assert_eq!(forwarded["X-Request-Id"], vec!["req-1".to_string()]);
+        assert_eq!(forwarded["authorization"], vec!["Bearer token".to_string()]);
+        assert_eq!(
+            forwarded["Content-Type"],
+            vec!["application/json".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_batch_entry_base64_body_serialization() {
+        let body = [0xff, 0x00, 0xfe, b'h', b'i'];
+        let batch_entry = BatchEntry::new("https://example.com/binary")
+            .body("replaced")
+            .body_bytes(&body);
+
+        let serialized = serde_json::to_value(&batch_entry).unwrap();
+
+        assert_eq!(
+            serialized,
+            json!({
+                "destination": "https://example.com/binary",
+                "headers": {},
+                "bodyBase64": "/wD+aGk="
+            })
+        );
+        let deserialized: BatchEntry = serde_json::from_value(serialized).unwrap();
+        assert_eq!(deserialized.body, None);
+        assert_eq!(
+            STANDARD.decode(deserialized.body_base64.unwrap()).unwrap(),
+            body
+        );
+    }
+
+    #[test]
+    fn test_message_response_result_round_trip() {
+        let bare_object = json!({
+            "messageId": "msg1",
+            "url": "https://example.com",
+            "deduplicated": false
+        });
+        let array = json!([
+            {"messageId": "msg2", "url": "https://example.com/a", "deduplicated": false},
+            {"messageId": "msg3", "url": "https://example.com/b", "deduplicated": true}
+        ]);
+
+        let result: MessageResponseResult = serde_json::from_value(bare_object.clone()).unwrap();
+        assert!(matches!(result, MessageResponseResult::URLResponse(_)));
+        assert_eq!(serde_json::to_value(&result).unwrap(), bare_object);
+
+        let result: MessageResponseResult = serde_json::from_value(array.clone()).unwrap();
+        assert!(matches!(
+            &result,
+            MessageResponseResult::URLGroupResponse(responses) if responses.len() == 2
+        ));
+        assert_eq!(serde_json::to_value(&result).unwrap(), array);
+    }
+
+    #[test]
+    fn test_batch_entry_multi_valued_header_serialization() {
+        let mut entry = BatchEntry::new("https://example.com").body("hello");
+        entry.headers.append(
+            "Upstash-Forward-Accept",
+            HeaderValue::from_static("text/html"),
+        );
+        entry.headers.append(
+            "Upstash-Forward-Accept",
+            HeaderValue::from_static("application/json"),
+        );
+
+        let serialized = serde_json::to_value(&entry).unwrap();
+        assert_eq!(
+            serialized["headers"]["upstash-forward-accept"],
+            "text/html, application/json"
+        );
+
+        entry.headers.append(
+            "Upstash-Forward-Set-Cookie",
+            HeaderValue::from_static("a=1; Expires=Wed, 21 Oct 2026 07:28:00 GMT"),
+        );
+        assert!(serde_json::to_value(&entry).is_ok());
+        entry.headers.append(
+            "Upstash-Forward-Set-Cookie",
+            HeaderValue::from_static("b=2"),
+        );
+        assert!(serde_json::to_value(&entry).is_err());
+    }
 }
@@ -1,89 +1,235 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
 use reqwest::Method;
 
 use crate::client::QstashClient;
+use crate::endpoints;
 use crate::errors::QstashError;
-use crate::message_types::{BatchEntry, Message, MessageResponseResult};
-use reqwest::header::HeaderMap;
+use crate::message_types::{
+    BatchEntry, CancelMessagesResponse, Message, MessageBodyExtractor, MessageResponse,
+    MessageResponseResult, PublishBody,
+};
+use crate::signing_keys::sign_body_for;
+use crate::url_groups::validate_url_group_name;
+use futures::stream::{self, Stream};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
 
 impl QstashClient {
+    /// Publishes a message to a URL or URL Group.
+    ///
+    /// Deduplication is controlled through the `Upstash-Deduplication-Id` and
+    /// `Upstash-Content-Based-Deduplication: true` headers. QStash keeps deduplication ids for a
+    /// fixed window of 10 minutes which can't be configured per message: a second message with
+    /// the same id (or the same body, headers and destination when content based) published
+    /// within that window is accepted but not delivered, and the response reports
+    /// `deduplicated: true`. Once the window has passed, the message is delivered again.
     pub async fn publish_message(
         &self,
         destination: &str,
         headers: HeaderMap,
-        body: Vec<u8>,
+        body: impl Into<PublishBody>,
     ) -> Result<MessageResponseResult, QstashError> {
+        let (mut headers, body) = prepare_body(headers, body.into());
+        self.insert_method_default(&mut headers);
         let request = self
             .client
             .get_request_builder(
                 Method::POST,
-                self.base_url
-                    .join(&format!("/v2/publish/{}", destination))
-                    .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
+                self.endpoint_url(&endpoints::publish(destination))?,
             )
             .headers(headers)
             .body(body);
 
-        let response = self
+        let response = self.client.send_request(request).await?;
+        let schedule_id = response
+            .headers()
+            .get("Upstash-Schedule-Id")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
+        let mut response = self
             .client
-            .send_request(request)
-            .await?
-            .json::<MessageResponseResult>()
-            .await
-            .map_err(QstashError::ResponseBodyParseError)?;
+            .parse_json::<MessageResponseResult>(response)
+            .await?;
+
+        if let Some(schedule_id) = schedule_id {
+            response.set_schedule_id(&schedule_id);
+        }
 
         Ok(response)
     }
 
+    /// Publishes a message like `publish_message`, but when QStash answers with a daily or burst
+    /// rate limit it sleeps until the limit resets and tries once more. The wait is capped like
+    /// the one of `QstashClientBuilder::wait_on_rate_limit`, which does the same for every
+    /// request of a client.
+    pub async fn publish_message_blocking_on_rate_limit(
+        &self,
+        destination: &str,
+        headers: HeaderMap,
+        body: impl Into<PublishBody>,
+    ) -> Result<MessageResponseResult, QstashError> {
+        let body = body.into();
+        match self
+            .publish_message(destination, headers.clone(), body.clone())
+            .await
+        {
+            Err(err) if self.client.wait_for_rate_limit_reset(&err).await => {
+                self.publish_message(destination, headers, body).await
+            }
+            result => result,
+        }
+    }
+
+    /// Publishes a message with the `Upstash-*` headers built from `options`.
+    /// When `options` names a queue the message is enqueued there instead.
+    pub async fn publish_message_with_options(
+        &self,
+        destination: &str,
+        options: PublishOptions,
+        body: impl Into<PublishBody>,
+    ) -> Result<MessageResponseResult, QstashError> {
+        let headers = options.to_headers()?;
+        match options.queue {
+            Some(queue_name) => {
+                self.enqueue_message(destination, &queue_name, headers, body)
+                    .await
+            }
+            None => self.publish_message(destination, headers, body).await,
+        }
+    }
+
+    /// Publishes a message that QStash delivers no earlier than `at`.
+    pub async fn publish_at(
+        &self,
+        destination: &str,
+        at: DateTime<Utc>,
+        options: PublishOptions,
+        body: impl Into<PublishBody>,
+    ) -> Result<MessageResponseResult, QstashError> {
+        self.publish_message_with_options(destination, options.not_before(at), body)
+            .await
+    }
+
+    /// Publishes a message to every endpoint of a URL Group.
+    ///
+    /// QStash answers with one entry per endpoint, so the response grows linearly with the size
+    /// of the group (roughly 100 bytes per endpoint) and is buffered in full before parsing.
+    pub async fn publish_to_url_group(
+        &self,
+        url_group_name: &str,
+        headers: HeaderMap,
+        body: impl Into<PublishBody>,
+    ) -> Result<Vec<MessageResponse>, QstashError> {
+        validate_url_group_name(url_group_name)?;
+        let response = self.publish_message(url_group_name, headers, body).await?;
+        Ok(response.into_responses())
+    }
+
     pub async fn enqueue_message(
         &self,
         destination: &str,
         queue_name: &str,
         headers: HeaderMap,
-        body: Vec<u8>,
+        body: impl Into<PublishBody>,
+    ) -> Result<MessageResponseResult, QstashError> {
+        let (headers, body) = prepare_body(headers, body.into());
+        self.enqueue_message_stream(destination, queue_name, headers, body)
+            .await
+    }
+
+    /// Enqueues a message whose body is sent as given, so a body built with
+    /// `reqwest::Body::wrap_stream` is streamed instead of buffered in full.
+    ///
+    /// The body is sent as `application/octet-stream` unless `headers` set a `Content-Type`.
+    pub async fn enqueue_message_stream(
+        &self,
+        destination: &str,
+        queue_name: &str,
+        mut headers: HeaderMap,
+        body: impl Into<reqwest::Body>,
     ) -> Result<MessageResponseResult, QstashError> {
+        if !headers.contains_key(CONTENT_TYPE) {
+            headers.insert(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/octet-stream"),
+            );
+        }
+        self.insert_method_default(&mut headers);
         let request = self
             .client
             .get_request_builder(
                 Method::POST,
-                self.base_url
-                    .join(&format!("/v2/enqueue/{}/{}", queue_name, destination))
-                    .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
+                self.endpoint_url(&endpoints::enqueue(queue_name, destination))?,
             )
             .headers(headers)
             .body(body);
 
         let response = self
             .client
-            .send_request(request)
-            .await?
-            .json::<MessageResponseResult>()
-            .await
-            .map_err(QstashError::ResponseBodyParseError)?;
+            .send_json::<MessageResponseResult>(request)
+            .await?;
 
         Ok(response)
     }
 
+    /// Enqueues a message and then fetches the queue, returning the response together with the
+    /// queue's current lag so producers can back off when consumers fall behind.
+    ///
+    /// The lag is read right after enqueueing and may already be stale when it is returned.
+    pub async fn enqueue_and_report_lag(
+        &self,
+        destination: &str,
+        queue_name: &str,
+        headers: HeaderMap,
+        body: impl Into<PublishBody>,
+    ) -> Result<(MessageResponseResult, i32), QstashError> {
+        let response = self
+            .enqueue_message(destination, queue_name, headers, body)
+            .await?;
+        let queue = self.get_queue(queue_name).await?;
+
+        Ok((response, queue.lag))
+    }
+
     pub async fn batch_messages(
         &self,
         batch_entries: Vec<BatchEntry>,
     ) -> Result<Vec<MessageResponseResult>, QstashError> {
+        self.send_batch(&batch_entries).await
+    }
+
+    /// Same as `batch_messages`, but pairs every entry with its result. QStash answers a batch
+    /// positionally, so this saves callers from zipping by hand when some entries target URL
+    /// groups and get a `URLGroupResponse` back.
+    pub async fn batch_messages_mapped(
+        &self,
+        batch_entries: Vec<BatchEntry>,
+    ) -> Result<Vec<(BatchEntry, MessageResponseResult)>, QstashError> {
+        let responses = self.send_batch(&batch_entries).await?;
+
+        Ok(batch_entries.into_iter().zip(responses).collect())
+    }
+
+    async fn send_batch(
+        &self,
+        batch_entries: &[BatchEntry],
+    ) -> Result<Vec<MessageResponseResult>, QstashError> {
+        // Serialized up front so entries that can't be sent fail before the request.
+        let body = serde_json::to_vec(batch_entries)
+            .map_err(|e| QstashError::InvalidHeader(e.to_string()))?;
         let request = self
             .client
-            .get_request_builder(
-                Method::POST,
-                self.base_url
-                    .join("/v2/batch")
-                    .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
-            )
-            .json(&batch_entries);
+            .get_request_builder(Method::POST, self.endpoint_url(&endpoints::batch())?)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body);
 
         let response = self
             .client
-            .send_request(request)
-            .await?
-            .json::<Vec<MessageResponseResult>>()
-            .await
-            .map_err(QstashError::ResponseBodyParseError)?;
+            .send_json::<Vec<MessageResponseResult>>(request)
+            .await?;
 
         Ok(response)
     }
@@ -91,34 +237,103 @@ impl QstashClient {
     pub async fn get_message(&self, message_id: &str) -> Result<Message, QstashError> {
         let request = self.client.get_request_builder(
             Method::GET,
-            self.base_url
-                .join(&format!("/v2/messages/{}", message_id))
-                .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
+            self.endpoint_url(&endpoints::message(message_id))?,
         );
 
-        let response = self
-            .client
-            .send_request(request)
-            .await?
-            .json::<Message>()
-            .await
-            .map_err(QstashError::ResponseBodyParseError)?;
+        let response = self.client.send_json::<Message>(request).await?;
 
         Ok(response)
     }
 
+    /// Fetches the body of a stored message and signs it with `signing_key`, returning the body
+    /// and a fresh `Upstash-Signature` for it, valid for five minutes.
+    ///
+    /// Meant for integration tests: the message can be replayed to a local receiver, which
+    /// accepts it when verifying against the same key. The body is only complete if the stored
+    /// message wasn't truncated, see `Message::body_truncated`.
+    pub async fn replay_message(
+        &self,
+        message_id: &str,
+        signing_key: &str,
+    ) -> Result<(Vec<u8>, String), QstashError> {
+        let message = self.get_message(message_id).await?;
+        let body = message.body.into_bytes();
+        let signature = sign_body_for(&body, &message.url, signing_key);
+
+        Ok((body, signature))
+    }
+
+    /// Streams the body of a stored message instead of buffering the whole `Message`.
+    ///
+    /// QStash returns the body inside the message JSON, so the document is still downloaded in
+    /// full, but only the unescaped body bytes are handed out as they arrive.
+    pub async fn get_message_body_stream(
+        &self,
+        message_id: &str,
+    ) -> Result<impl Stream<Item = Result<Bytes, QstashError>>, QstashError> {
+        let request = self.client.get_request_builder(
+            Method::GET,
+            self.endpoint_url(&endpoints::message(message_id))?,
+        );
+
+        let response = self.client.send_request(request).await?;
+
+        Ok(stream::try_unfold(
+            (response, MessageBodyExtractor::default()),
+            |(mut response, mut extractor)| async move {
+                while !extractor.is_done() {
+                    let Some(chunk) = response.chunk().await.map_err(QstashError::RequestFailed)?
+                    else {
+                        extractor
+                            .finish()
+                            .map_err(QstashError::ResponseStreamParseError)?;
+                        break;
+                    };
+                    let body = extractor
+                        .feed(&chunk)
+                        .map_err(QstashError::ResponseStreamParseError)?;
+                    if !body.is_empty() {
+                        return Ok(Some((Bytes::from(body), (response, extractor))));
+                    }
+                }
+                Ok(None)
+            },
+        ))
+    }
+
     pub async fn cancel_message(&self, message_id: &str) -> Result<(), QstashError> {
         let request = self.client.get_request_builder(
             Method::DELETE,
-            self.base_url
-                .join(&format!("/v2/messages/{}", message_id))
-                .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
+            self.endpoint_url(&endpoints::message(message_id))?,
         );
 
         self.client.send_request(request).await?;
         Ok(())
     }
 
+    /// Cancels every pending message of the account and returns how many were cancelled.
+    ///
+    /// This can't be undone, so it refuses to run with `QstashError::ConfirmationRequired`
+    /// unless `confirm` is `true`.
+    pub async fn cancel_all_messages(&self, confirm: bool) -> Result<usize, QstashError> {
+        if !confirm {
+            return Err(QstashError::ConfirmationRequired(
+                "cancel all messages".to_string(),
+            ));
+        }
+
+        let request = self
+            .client
+            .get_request_builder(Method::DELETE, self.endpoint_url(&endpoints::messages())?);
+
+        let response = self
+            .client
+            .send_json::<CancelMessagesResponse>(request)
+            .await?;
+
+        Ok(response.cancelled)
+    }
+
     pub async fn bulk_cancel_messages(&self, message_ids: Vec<String>) -> Result<(), QstashError> {
         println!(
             "{}",
@@ -129,12 +344,7 @@ impl QstashClient {
         );
         let request = self
             .client
-            .get_request_builder(
-                Method::DELETE,
-                self.base_url
-                    .join("/v2/messages")
-                    .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
-            )
+            .get_request_builder(Method::DELETE, self.endpoint_url(&endpoints::messages())?)
             .json(&json!({
                 "messageIds": message_ids,
             }));
@@ -142,6 +352,235 @@ impl QstashClient {
         self.client.send_request(request).await?;
         Ok(())
     }
+
+    // Makes QStash's POST default explicit when the client is built with
+    // `explicit_method_default`, leaving a method set by the caller untouched.
+    fn insert_method_default(&self, headers: &mut HeaderMap) {
+        if self.explicit_method_default && !headers.contains_key("Upstash-Method") {
+            headers.insert("Upstash-Method", HeaderValue::from_static("POST"));
+        }
+    }
+}
+
+/// Headers that are never forwarded to the destination unless the denylist is overridden
+/// with `PublishOptions::forward_header_denylist`.
+pub const DEFAULT_FORWARD_HEADER_DENYLIST: [&str; 3] =
+    ["Host", "Authorization", "Proxy-Authorization"];
+
+/// Typed alternative to building the `Upstash-*` publish headers by hand.
+#[derive(Debug, Clone)]
+pub struct PublishOptions {
+    delay: Option<Duration>,
+    not_before: Option<DateTime<Utc>>,
+    queue: Option<String>,
+    retries: Option<u32>,
+    method: Option<Method>,
+    callback: Option<String>,
+    callback_method: Option<Method>,
+    failure_callback: Option<String>,
+    deduplication_id: Option<String>,
+    control_headers: Vec<(String, String)>,
+    forward_headers: Vec<(String, String)>,
+    forward_header_denylist: Vec<String>,
+}
+
+impl Default for PublishOptions {
+    fn default() -> Self {
+        PublishOptions {
+            delay: None,
+            not_before: None,
+            queue: None,
+            retries: None,
+            method: None,
+            callback: None,
+            callback_method: None,
+            failure_callback: None,
+            deduplication_id: None,
+            control_headers: Vec::new(),
+            forward_headers: Vec::new(),
+            forward_header_denylist: DEFAULT_FORWARD_HEADER_DENYLIST
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl PublishOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Delays the delivery of the message, sent as `Upstash-Delay` in whole seconds.
+    /// A zero delay is still sent, as `0s`, rather than treated as unset.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Holds the message until `at`, sent as `Upstash-Not-Before` in Unix seconds.
+    pub fn not_before(mut self, at: DateTime<Utc>) -> Self {
+        self.not_before = Some(at);
+        self
+    }
+
+    /// Enqueues the message to `queue_name` instead of publishing it directly.
+    pub fn queue(mut self, queue_name: &str) -> Self {
+        self.queue = Some(queue_name.to_string());
+        self
+    }
+
+    /// How many times QStash retries a failed delivery, sent as `Upstash-Retries`.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = Some(retries);
+        self
+    }
+
+    /// The HTTP method the message is delivered with, sent as `Upstash-Method`.
+    pub fn method(mut self, method: Method) -> Self {
+        self.method = Some(method);
+        self
+    }
+
+    /// URL called with the destination's response once the message is delivered, sent as
+    /// `Upstash-Callback`.
+    pub fn callback(mut self, url: &str) -> Self {
+        self.callback = Some(url.to_string());
+        self
+    }
+
+    /// The HTTP method the callback is invoked with, sent as `Upstash-Callback-Method`.
+    pub fn callback_method(mut self, method: Method) -> Self {
+        self.callback_method = Some(method);
+        self
+    }
+
+    /// URL called once every delivery attempt has failed, sent as `Upstash-Failure-Callback`.
+    pub fn failure_callback(mut self, url: &str) -> Self {
+        self.failure_callback = Some(url.to_string());
+        self
+    }
+
+    /// Drops messages published with an id already seen recently, sent as
+    /// `Upstash-Deduplication-Id`.
+    pub fn deduplication_id(mut self, deduplication_id: &str) -> Self {
+        self.deduplication_id = Some(deduplication_id.to_string());
+        self
+    }
+
+    /// Sends a header to QStash verbatim, such as `Upstash-Retries` or `Content-Type`.
+    /// Typed options like `delay` take precedence over a control header of the same name.
+    pub fn control_header(mut self, name: &str, value: &str) -> Self {
+        self.control_headers
+            .push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Forwards a header to the destination, sent as `Upstash-Forward-<name>`.
+    /// Headers on the denylist are dropped when the options are turned into headers.
+    pub fn forward_header(mut self, name: &str, value: &str) -> Self {
+        self.forward_headers
+            .push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Replaces `DEFAULT_FORWARD_HEADER_DENYLIST` with `denylist`. Names are matched
+    /// case-insensitively; pass an empty list to forward every header.
+    pub fn forward_header_denylist<I, S>(mut self, denylist: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.forward_header_denylist = denylist.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub(crate) fn to_headers(&self) -> Result<HeaderMap, QstashError> {
+        let mut headers = HeaderMap::new();
+
+        for (name, value) in &self.control_headers {
+            append_header(&mut headers, name, value)?;
+        }
+
+        if let Some(delay) = self.delay {
+            insert_header(
+                &mut headers,
+                "Upstash-Delay",
+                &format!("{}s", delay.as_secs()),
+            )?;
+        }
+
+        if let Some(at) = self.not_before {
+            insert_header(
+                &mut headers,
+                "Upstash-Not-Before",
+                &at.timestamp().to_string(),
+            )?;
+        }
+
+        if let Some(retries) = self.retries {
+            insert_header(&mut headers, "Upstash-Retries", &retries.to_string())?;
+        }
+
+        if let Some(method) = &self.method {
+            insert_header(&mut headers, "Upstash-Method", method.as_str())?;
+        }
+
+        if let Some(callback) = &self.callback {
+            insert_header(&mut headers, "Upstash-Callback", callback)?;
+        }
+
+        if let Some(method) = &self.callback_method {
+            insert_header(&mut headers, "Upstash-Callback-Method", method.as_str())?;
+        }
+
+        if let Some(failure_callback) = &self.failure_callback {
+            insert_header(&mut headers, "Upstash-Failure-Callback", failure_callback)?;
+        }
+
+        if let Some(deduplication_id) = &self.deduplication_id {
+            insert_header(&mut headers, "Upstash-Deduplication-Id", deduplication_id)?;
+        }
+
+        for (name, value) in self.forward_headers.iter().filter(|(name, _)| {
+            !self
+                .forward_header_denylist
+                .iter()
+                .any(|denied| denied.eq_ignore_ascii_case(name))
+        }) {
+            append_header(&mut headers, &format!("Upstash-Forward-{}", name), value)?;
+        }
+
+        Ok(headers)
+    }
+}
+
+fn insert_header(
+    headers: &mut HeaderMap,
+    name: &'static str,
+    value: &str,
+) -> Result<(), QstashError> {
+    let value =
+        HeaderValue::from_str(value).map_err(|e| QstashError::InvalidHeader(e.to_string()))?;
+    headers.insert(name, value);
+    Ok(())
+}
+
+fn append_header(headers: &mut HeaderMap, name: &str, value: &str) -> Result<(), QstashError> {
+    let name = HeaderName::from_bytes(name.as_bytes())
+        .map_err(|e| QstashError::InvalidHeader(e.to_string()))?;
+    let value =
+        HeaderValue::from_str(value).map_err(|e| QstashError::InvalidHeader(e.to_string()))?;
+    headers.append(name, value);
+    Ok(())
+}
+
+// Fills in the body's default content type unless the caller already set one.
+fn prepare_body(mut headers: HeaderMap, body: PublishBody) -> (HeaderMap, Vec<u8>) {
+    if !headers.contains_key(CONTENT_TYPE) {
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static(body.content_type()));
+    }
+    (headers, body.into_bytes())
 }
 
 #[cfg(test)]
@@ -150,14 +589,22 @@ mod tests {
 
     use crate::client::QstashClient;
     use crate::errors::QstashError;
-    use crate::message_types::{BatchEntry, Message, MessageResponse, MessageResponseResult};
+    use crate::message_types::{
+        BatchEntry, Message, MessageResponse, MessageResponseResult, PublishBody,
+    };
+    use crate::messages::PublishOptions;
+    use bytes::Bytes;
+    use chrono::{DateTime, Utc};
+    use futures::TryStreamExt;
     use httpmock::Method::{DELETE, GET, POST};
     use httpmock::MockServer;
     use reqwest::header::{HeaderMap, HeaderValue};
+    use reqwest::Method;
     use reqwest::StatusCode;
     use reqwest::Url;
     use serde_json::json;
     use std::collections::HashMap;
+    use std::time::Duration;
 
     #[tokio::test]
     async fn test_publish_message_success_single_response() {
@@ -170,6 +617,7 @@ mod tests {
             message_id: "msg123".to_string(),
             url: Some("https://example.com/publish".to_string()),
             deduplicated: Some(false),
+            schedule_id: None,
         });
         let publish_mock = server.mock(|when, then| {
             when.method(POST)
@@ -206,11 +654,13 @@ mod tests {
                 message_id: "msg123".to_string(),
                 url: Some("https://example.com/publish".to_string()),
                 deduplicated: Some(false),
+                schedule_id: None,
             },
             MessageResponse {
                 message_id: "msg124".to_string(),
                 url: Some("https://example.com/publish".to_string()),
                 deduplicated: Some(true),
+                schedule_id: None,
             },
         ]);
         let publish_mock = server.mock(|when, then| {
@@ -307,6 +757,7 @@ mod tests {
             message_id: "msg125".to_string(),
             url: Some("https://example.com/enqueue".to_string()),
             deduplicated: Some(false),
+            schedule_id: None,
         });
         let enqueue_mock = server.mock(|when, then| {
             when.method(POST)
@@ -407,6 +858,47 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn test_enqueue_message_stream() {
+        let server = MockServer::start();
+        let enqueue_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/enqueue/queue1/https://example.com/enqueue")
+                .header("Authorization", "Bearer test_api_key")
+                .header("content-type", "application/octet-stream")
+                .body("first chunk, second chunk");
+            then.status(StatusCode::OK.as_u16())
+                .json_body(json!({"messageId": "msg123"}));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let chunks: Vec<Result<&'static str, std::io::Error>> =
+            vec![Ok("first chunk, "), Ok("second chunk")];
+        let body = reqwest::Body::wrap_stream(futures::stream::iter(chunks));
+        let result = client
+            .enqueue_message_stream(
+                "https://example.com/enqueue",
+                "queue1",
+                HeaderMap::new(),
+                body,
+            )
+            .await;
+        enqueue_mock.assert();
+        assert_eq!(
+            result.unwrap(),
+            MessageResponseResult::URLResponse(MessageResponse {
+                message_id: "msg123".to_string(),
+                url: None,
+                deduplicated: None,
+                schedule_id: None,
+            })
+        );
+    }
+
     #[tokio::test]
     async fn test_batch_messages_success() {
         let server = MockServer::start();
@@ -420,6 +912,8 @@ mod tests {
                     headers
                 },
                 body: Some("Message 1".to_string()),
+                body_base64: None,
+                failure_callback: None,
             },
             BatchEntry {
                 destination: "https://example.com/publish2".to_string(),
@@ -430,6 +924,8 @@ mod tests {
                     headers
                 },
                 body: Some("Message 2".to_string()),
+                body_base64: None,
+                failure_callback: None,
             },
         ];
         let expected_response = vec![
@@ -437,17 +933,20 @@ mod tests {
                 message_id: "msg126".to_string(),
                 url: Some("https://example.com/publish1".to_string()),
                 deduplicated: Some(false),
+                schedule_id: None,
             }),
             MessageResponseResult::URLGroupResponse(vec![
                 MessageResponse {
                     message_id: "msg127".to_string(),
                     url: Some("https://example.com/publish2".to_string()),
                     deduplicated: Some(true),
+                    schedule_id: None,
                 },
                 MessageResponse {
                     message_id: "msg128".to_string(),
                     url: Some("https://example.com/publish2".to_string()),
                     deduplicated: Some(false),
+                    schedule_id: None,
                 },
             ]),
         ];
@@ -520,6 +1019,8 @@ mod tests {
                 headers
             },
             body: Some("Message 1".to_string()),
+            body_base64: None,
+            failure_callback: None,
         }];
         let batch_mock = server.mock(|when, then| {
             when.method(POST)
@@ -556,6 +1057,8 @@ mod tests {
                 headers
             },
             body: Some("Message 1".to_string()),
+            body_base64: None,
+            failure_callback: None,
         }];
         let batch_mock = server.mock(|when, then| {
             when.method(POST)
@@ -597,6 +1100,7 @@ mod tests {
             ]),
             body: "{\"key\":\"value\"}".to_string(),
             created_at: 1625097600,
+            body_truncated: false,
         };
         let get_mock = server.mock(|when, then| {
             when.method(GET)
@@ -791,6 +1295,7 @@ mod tests {
             message_id: "msg129".to_string(),
             url: Some("https://example.com/publish".to_string()),
             deduplicated: Some(false),
+            schedule_id: None,
         });
         let publish_mock = server.mock(|when, then| {
             when.method(POST)
@@ -829,6 +1334,7 @@ mod tests {
             message_id: "msg130".to_string(),
             url: Some("https://example.com/enqueue".to_string()),
             deduplicated: Some(false),
+            schedule_id: None,
         });
         let enqueue_mock = server.mock(|when, then| {
             when.method(POST)
@@ -858,4 +1364,820 @@ mod tests {
         let response = result.unwrap();
         assert_eq!(response, expected_response);
     }
+
+    async fn assert_publish_body(body: PublishBody, content_type: &str, expected_body: &[u8]) {
+        let server = MockServer::start();
+        let expected_response = MessageResponseResult::URLResponse(MessageResponse {
+            message_id: "msg131".to_string(),
+            url: Some("https://example.com/publish".to_string()),
+            deduplicated: Some(false),
+            schedule_id: None,
+        });
+        let publish_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/publish/https://example.com/publish")
+                .header("Authorization", "Bearer test_api_key")
+                .header("content-type", content_type)
+                .body(String::from_utf8_lossy(expected_body));
+            then.status(StatusCode::OK.as_u16())
+                .header("content-type", "application/json")
+                .json_body_obj(&expected_response);
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let result = client
+            .publish_message("https://example.com/publish", HeaderMap::new(), body)
+            .await;
+        publish_mock.assert();
+        assert_eq!(result.unwrap(), expected_response);
+    }
+
+    #[tokio::test]
+    async fn test_publish_message_json_body() {
+        assert_publish_body(
+            PublishBody::Json(json!({"key": "value"})),
+            "application/json",
+            b"{\"key\":\"value\"}",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_publish_message_text_body() {
+        assert_publish_body(
+            PublishBody::Text("Hello World".to_string()),
+            "text/plain; charset=utf-8",
+            b"Hello World",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_publish_message_bytes_body() {
+        assert_publish_body(
+            PublishBody::Bytes(b"raw bytes".to_vec()),
+            "application/octet-stream",
+            b"raw bytes",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_publish_message_schedule_id() {
+        let server = MockServer::start();
+        let publish_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/publish/https://example.com/scheduled");
+            then.status(StatusCode::OK.as_u16())
+                .header("content-type", "application/json")
+                .header("Upstash-Schedule-Id", "scd_123")
+                .json_body(json!({"messageId": "msg132"}));
+        });
+        let plain_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/publish/https://example.com/plain");
+            then.status(StatusCode::OK.as_u16())
+                .header("content-type", "application/json")
+                .json_body(json!({"messageId": "msg133"}));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let result = client
+            .publish_message("https://example.com/scheduled", HeaderMap::new(), "Hello")
+            .await;
+        publish_mock.assert();
+        match result.unwrap() {
+            MessageResponseResult::URLResponse(response) => {
+                assert_eq!(response.schedule_id, Some("scd_123".to_string()))
+            }
+            _ => panic!("Expected URLResponse"),
+        }
+
+        let result = client
+            .publish_message("https://example.com/plain", HeaderMap::new(), "Hello")
+            .await;
+        plain_mock.assert();
+        match result.unwrap() {
+            MessageResponseResult::URLResponse(response) => assert_eq!(response.schedule_id, None),
+            _ => panic!("Expected URLResponse"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_publish_to_url_group_many_endpoints() {
+        let server = MockServer::start();
+        let responses: Vec<serde_json::Value> = (0..50)
+            .map(|i| {
+                json!({
+                    "messageId": format!("msg_{}", i),
+                    "url": format!("https://example.com/endpoint/{}", i)
+                })
+            })
+            .collect();
+        let publish_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/publish/large-group")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .header("content-type", "application/json")
+                .json_body(json!(responses));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let result = client
+            .publish_to_url_group("large-group", HeaderMap::new(), "Hello")
+            .await;
+        publish_mock.assert();
+        let responses = result.unwrap();
+        assert_eq!(responses.len(), 50);
+        assert_eq!(responses[49].message_id, "msg_49");
+        assert_eq!(
+            responses[49].url,
+            Some("https://example.com/endpoint/49".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_enqueue_and_report_lag() {
+        let server = MockServer::start();
+        let expected_response = MessageResponseResult::URLResponse(MessageResponse {
+            message_id: "msg125".to_string(),
+            url: Some("https://example.com/enqueue".to_string()),
+            deduplicated: Some(false),
+            schedule_id: None,
+        });
+        let enqueue_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/enqueue/queue1/https://example.com/enqueue")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .header("content-type", "application/json")
+                .json_body_obj(&expected_response);
+        });
+        let get_queue_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/queues/queue1")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16()).json_body(json!({
+                "createdAt": 1_623_345_678_001i64,
+                "updatedAt": 1_623_345_678_002i64,
+                "name": "queue1",
+                "parallelism": 2,
+                "lag": 42
+            }));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let (response, lag) = client
+            .enqueue_and_report_lag(
+                "https://example.com/enqueue",
+                "queue1",
+                HeaderMap::new(),
+                "Hello",
+            )
+            .await
+            .expect("Failed to enqueue message");
+        enqueue_mock.assert();
+        get_queue_mock.assert();
+        assert_eq!(response, expected_response);
+        assert_eq!(lag, 42);
+    }
+
+    #[test]
+    fn test_publish_options_delay_headers() {
+        let headers = PublishOptions::new()
+            .delay(Duration::from_secs(90))
+            .to_headers()
+            .unwrap();
+        assert_eq!(headers.get("Upstash-Delay").unwrap(), "90s");
+
+        let headers = PublishOptions::new().to_headers().unwrap();
+        assert!(headers.get("Upstash-Delay").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_publish_message_with_zero_delay() {
+        let server = MockServer::start();
+        let publish_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/publish/https://example.com/publish")
+                .header("Authorization", "Bearer test_api_key")
+                .header("Upstash-Delay", "0s");
+            then.status(StatusCode::OK.as_u16())
+                .json_body(json!({"messageId": "msg123"}));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let options = PublishOptions::new().delay(Duration::ZERO);
+        let result = client
+            .publish_message_with_options("https://example.com/publish", options, "Hello")
+            .await;
+        publish_mock.assert();
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_publish_message_accepted() {
+        let server = MockServer::start();
+        let publish_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/publish/https://example.com/publish")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::ACCEPTED.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!({"messageId": "msg123", "url": "https://example.com/publish"}));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let result = client
+            .publish_message("https://example.com/publish", HeaderMap::new(), "Hello")
+            .await;
+        publish_mock.assert();
+        assert_eq!(
+            result.unwrap(),
+            MessageResponseResult::URLResponse(MessageResponse {
+                message_id: "msg123".to_string(),
+                url: Some("https://example.com/publish".to_string()),
+                deduplicated: None,
+                schedule_id: None,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_publish_message_explicit_method_default() {
+        let server = MockServer::start();
+        let default_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/publish/https://example.com/default")
+                .header("Authorization", "Bearer test_api_key")
+                .header("Upstash-Method", "POST");
+            then.status(StatusCode::OK.as_u16())
+                .json_body(json!({"messageId": "msg123"}));
+        });
+        let put_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/publish/https://example.com/put")
+                .header("Authorization", "Bearer test_api_key")
+                .header("Upstash-Method", "PUT");
+            then.status(StatusCode::OK.as_u16())
+                .json_body(json!({"messageId": "msg456"}));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .explicit_method_default(true)
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let result = client
+            .publish_message("https://example.com/default", HeaderMap::new(), "Hello")
+            .await;
+        assert!(result.is_ok());
+        default_mock.assert();
+
+        let mut headers = HeaderMap::new();
+        headers.insert("Upstash-Method", HeaderValue::from_static("PUT"));
+        let result = client
+            .publish_message("https://example.com/put", headers, "Hello")
+            .await;
+        assert!(result.is_ok());
+        put_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_publish_at_sets_not_before() {
+        let server = MockServer::start();
+        let publish_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/publish/https://example.com/publish")
+                .header("Authorization", "Bearer test_api_key")
+                .header("Upstash-Not-Before", "1735689600");
+            then.status(StatusCode::OK.as_u16())
+                .json_body(json!({"messageId": "msg123"}));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let at = DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let result = client
+            .publish_at(
+                "https://example.com/publish",
+                at,
+                PublishOptions::new(),
+                "Hello",
+            )
+            .await;
+        publish_mock.assert();
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_publish_message_with_options_enqueues_to_queue() {
+        let server = MockServer::start();
+        let enqueue_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/enqueue/queue1/https://example.com/publish")
+                .header("Authorization", "Bearer test_api_key")
+                .header("Upstash-Delay", "5s");
+            then.status(StatusCode::OK.as_u16())
+                .json_body(json!({"messageId": "msg123"}));
+        });
+        let publish_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/publish/https://example.com/publish");
+            then.status(StatusCode::OK.as_u16())
+                .json_body(json!({"messageId": "msg456"}));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let options = PublishOptions::new()
+            .queue("queue1")
+            .delay(Duration::from_secs(5));
+        let result = client
+            .publish_message_with_options("https://example.com/publish", options, "Hello")
+            .await;
+        enqueue_mock.assert();
+        publish_mock.assert_hits(0);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_publish_options_forward_header_denylist() {
+        let headers = PublishOptions::new()
+            .forward_header("X-Request-Id", "req-1")
+            .forward_header("authorization", "Bearer secret")
+            .forward_header("Host", "internal.example.com")
+            .to_headers()
+            .unwrap();
+        assert_eq!(
+            headers.get("Upstash-Forward-X-Request-Id").unwrap(),
+            "req-1"
+        );
+        assert!(headers.get("Upstash-Forward-Authorization").is_none());
+        assert!(headers.get("Upstash-Forward-Host").is_none());
+
+        let headers = PublishOptions::new()
+            .forward_header_denylist(["X-Request-Id"])
+            .forward_header("X-Request-Id", "req-1")
+            .forward_header("Authorization", "Bearer token")
+            .to_headers()
+            .unwrap();
+        assert!(headers.get("Upstash-Forward-X-Request-Id").is_none());
+        assert_eq!(
+            headers.get("Upstash-Forward-Authorization").unwrap(),
+            "Bearer token"
+        );
+    }
+
+    #[test]
+    fn test_publish_options_callback_method() {
+        let headers = PublishOptions::new()
+            .callback_method(Method::PUT)
+            .to_headers()
+            .unwrap();
+        assert_eq!(headers.get("Upstash-Callback-Method").unwrap(), "PUT");
+
+        let headers = PublishOptions::new().to_headers().unwrap();
+        assert!(headers.get("Upstash-Callback-Method").is_none());
+    }
+
+    #[test]
+    fn test_publish_options_control_and_forward_headers() {
+        let headers = PublishOptions::new()
+            .control_header("Upstash-Retries", "2")
+            .control_header("Content-Type", "application/json")
+            .forward_header("Upstash-Retries", "5")
+            .forward_header("X-Tenant", "acme")
+            .to_headers()
+            .unwrap();
+
+        assert_eq!(headers.get("Upstash-Retries").unwrap(), "2");
+        assert_eq!(headers.get("Content-Type").unwrap(), "application/json");
+        assert_eq!(headers.get("Upstash-Forward-Upstash-Retries").unwrap(), "5");
+        assert_eq!(headers.get("Upstash-Forward-X-Tenant").unwrap(), "acme");
+        assert!(headers.get("X-Tenant").is_none());
+        assert_eq!(headers.len(), 4);
+
+        let headers = PublishOptions::new()
+            .control_header("Upstash-Delay", "30s")
+            .delay(Duration::from_secs(10))
+            .to_headers()
+            .unwrap();
+        assert_eq!(headers.get_all("Upstash-Delay").iter().count(), 1);
+        assert_eq!(headers.get("Upstash-Delay").unwrap(), "10s");
+
+        assert!(matches!(
+            PublishOptions::new()
+                .control_header("Invalid Name", "value")
+                .to_headers(),
+            Err(QstashError::InvalidHeader(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_message_body_stream() {
+        let server = MockServer::start();
+        let body = "{\"key\":\"value\"}\n".repeat(50_000);
+        let message = Message {
+            message_id: "msg123".to_string(),
+            url: "https://example.com/publish".to_string(),
+            body: body.clone(),
+            ..Default::default()
+        };
+        let get_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/messages/msg123")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .header("content-type", "application/json")
+                .json_body_obj(&message);
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let chunks: Vec<Bytes> = client
+            .get_message_body_stream("msg123")
+            .await
+            .expect("Failed to request message body")
+            .try_collect()
+            .await
+            .expect("Failed to stream message body");
+
+        get_mock.assert();
+        assert_eq!(chunks.concat(), body.into_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_all_messages_requires_confirmation() {
+        let server = MockServer::start();
+        let cancel_all_mock = server.mock(|when, then| {
+            when.method(DELETE)
+                .path("/v2/messages")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .json_body(json!({"cancelled": 12}));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let result = client.cancel_all_messages(false).await;
+        assert!(matches!(result, Err(QstashError::ConfirmationRequired(_))));
+        cancel_all_mock.assert_hits(0);
+
+        let result = client.cancel_all_messages(true).await;
+        assert_eq!(result.unwrap(), 12);
+        cancel_all_mock.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn test_batch_messages_mapped() {
+        let server = MockServer::start();
+        let batch_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/batch")
+                .header("Authorization", "Bearer test_api_key")
+                .json_body(json!([
+                    {"destination": "my-group", "headers": {}},
+                    {"destination": "https://example.com/single", "headers": {}}
+                ]));
+            then.status(StatusCode::OK.as_u16())
+                .header("content-type", "application/json")
+                .json_body(json!([
+                    [
+                        {"messageId": "msg1", "url": "https://example.com/a"},
+                        {"messageId": "msg2", "url": "https://example.com/b"}
+                    ],
+                    {"messageId": "msg3", "url": "https://example.com/single"}
+                ]));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let result = client
+            .batch_messages_mapped(vec![
+                BatchEntry::new("my-group"),
+                BatchEntry::new("https://example.com/single"),
+            ])
+            .await
+            .unwrap();
+
+        batch_mock.assert();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].0.destination, "my-group");
+        assert!(matches!(
+            &result[0].1,
+            MessageResponseResult::URLGroupResponse(responses) if responses.len() == 2
+        ));
+        assert_eq!(result[1].0.destination, "https://example.com/single");
+        assert!(matches!(
+            &result[1].1,
+            MessageResponseResult::URLResponse(response) if response.message_id == "msg3"
+        ));
+    }
+
+    #[test]
+    fn test_publish_options_fully_populated_headers() {
+        let headers = PublishOptions::new()
+            .delay(Duration::from_secs(30))
+            .retries(3)
+            .method(Method::PUT)
+            .callback("https://example.com/callback")
+            .callback_method(Method::POST)
+            .failure_callback("https://example.com/failure")
+            .deduplication_id("order-42")
+            .forward_header("X-Tenant", "acme")
+            .to_headers()
+            .unwrap();
+
+        let mut headers: Vec<_> = headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.to_str().unwrap()))
+            .collect();
+        headers.sort();
+        assert_eq!(
+            headers,
+            vec![
+                ("upstash-callback", "https://example.com/callback"),
+                ("upstash-callback-method", "POST"),
+                ("upstash-deduplication-id", "order-42"),
+                ("upstash-delay", "30s"),
+                ("upstash-failure-callback", "https://example.com/failure"),
+                ("upstash-forward-x-tenant", "acme"),
+                ("upstash-method", "PUT"),
+                ("upstash-retries", "3"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_publish_message_with_typed_options() {
+        let server = MockServer::start();
+        let publish_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/publish/https://example.com/publish")
+                .header("Authorization", "Bearer test_api_key")
+                .header("Upstash-Retries", "0")
+                .header("Upstash-Method", "PATCH")
+                .header("Upstash-Deduplication-Id", "order-42");
+            then.status(StatusCode::OK.as_u16())
+                .json_body(json!({"messageId": "msg123"}));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let options = PublishOptions::new()
+            .retries(0)
+            .method(Method::PATCH)
+            .deduplication_id("order-42");
+
+        let result = client
+            .publish_message_with_options("https://example.com/publish", options, "Hello")
+            .await;
+
+        publish_mock.assert();
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_publish_message_forwards_multi_valued_headers() {
+        let server = MockServer::start();
+        let publish_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/publish/https://example.com")
+                .matches(|req| {
+                    let cookies: Vec<&str> = req
+                        .headers
+                        .iter()
+                        .flatten()
+                        .filter(|(name, _)| name.eq_ignore_ascii_case("Upstash-Forward-Set-Cookie"))
+                        .map(|(_, value)| value.as_str())
+                        .collect();
+                    cookies == ["a=1", "b=2"]
+                });
+            then.status(200).json_body(json!({"messageId": "msg123"}));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let options = PublishOptions::new()
+            .forward_header("Set-Cookie", "a=1")
+            .forward_header("Set-Cookie", "b=2");
+
+        let headers = options.to_headers().unwrap();
+        assert_eq!(
+            headers
+                .get_all("Upstash-Forward-Set-Cookie")
+                .iter()
+                .collect::<Vec<_>>(),
+            vec!["a=1", "b=2"]
+        );
+
+        let result = client
+            .publish_message_with_options("https://example.com", options, "hello")
+            .await;
+
+        publish_mock.assert();
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_publish_message_blocking_on_rate_limit_retries_once() {
+        let server = MockServer::start_async().await;
+        let rate_limited_mock = server
+            .mock_async(|when, then| {
+                when.method(POST).path("/v2/publish/https://example.com");
+                then.status(StatusCode::TOO_MANY_REQUESTS.as_u16())
+                    .header("RateLimit-Limit", "1000")
+                    .header("RateLimit-Reset", "500")
+                    .body("Rate limit exceeded");
+            })
+            .await;
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let publish = tokio::spawn({
+            let client = client.clone();
+            async move {
+                client
+                    .publish_message_blocking_on_rate_limit(
+                        "https://example.com",
+                        HeaderMap::new(),
+                        "hello",
+                    )
+                    .await
+            }
+        });
+        // Swap the rate limited answer for a successful one while the client waits out the
+        // 500ms reset.
+        while rate_limited_mock.hits_async().await == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        rate_limited_mock.delete_async().await;
+        let success_mock = server
+            .mock_async(|when, then| {
+                when.method(POST).path("/v2/publish/https://example.com");
+                then.status(200).json_body(json!({"messageId": "msg123"}));
+            })
+            .await;
+
+        let response = publish.await.unwrap().unwrap();
+
+        success_mock.assert_async().await;
+        let message_ids: Vec<_> = response
+            .into_responses()
+            .into_iter()
+            .map(|response| response.message_id)
+            .collect();
+        assert_eq!(message_ids, vec!["msg123"]);
+    }
+
+    #[tokio::test]
+    async fn test_publish_message_blocking_on_rate_limit_returns_other_errors() {
+        let server = MockServer::start();
+        let publish_mock = server.mock(|when, then| {
+            when.method(POST).path("/v2/publish/https://example.com");
+            then.status(StatusCode::BAD_REQUEST.as_u16());
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let result = client
+            .publish_message_blocking_on_rate_limit(
+                "https://example.com",
+                HeaderMap::new(),
+                "hello",
+            )
+            .await;
+
+        publish_mock.assert_hits(1);
+        assert!(matches!(result, Err(QstashError::RequestFailed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_replay_message_signature_verifies() {
+        let server = MockServer::start();
+        let get_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/messages/msg123")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(200).json_body(json!({
+                "messageId": "msg123",
+                "url": "https://example.com/receiver",
+                "body": "{\"order\":1}"
+            }));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let (body, signature) = client
+            .replay_message("msg123", "signing_key")
+            .await
+            .unwrap();
+
+        get_mock.assert();
+        assert_eq!(body, b"{\"order\":1}");
+        let claims =
+            crate::signing_keys::verify_signature(&body, &signature, "signing_key", "signing_key")
+                .unwrap();
+        assert_eq!(claims.sub, "https://example.com/receiver");
+        assert!(
+            crate::signing_keys::verify_signature(&body, &signature, "other_key", "other_key")
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_messages_rejects_repeated_set_cookie() {
+        let server = MockServer::start();
+        let batch_mock = server.mock(|when, then| {
+            when.method(POST).path("/v2/batch");
+            then.status(200).json_body(json!([]));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let mut entry = BatchEntry::new("https://example.com").body("hello");
+        entry.headers.append(
+            "Upstash-Forward-Set-Cookie",
+            HeaderValue::from_static("a=1"),
+        );
+        entry.headers.append(
+            "Upstash-Forward-Set-Cookie",
+            HeaderValue::from_static("b=2"),
+        );
+
+        let result = client.batch_messages(vec![entry]).await;
+
+        batch_mock.assert_hits(0);
+        assert!(matches!(result, Err(QstashError::InvalidHeader(_))));
+    }
 }
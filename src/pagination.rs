@@ -0,0 +1,116 @@
+use crate::dead_letter_queue::{DLQMessage, DLQMessagesList};
+use crate::events_types::{Event, EventsResponse};
+
+/// A single page of results from a list endpoint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    /// The items in this page.
+    pub items: Vec<T>,
+    /// A cursor to request the next page with. `None` once the last page has been reached.
+    pub cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    pub fn new(items: Vec<T>, cursor: Option<String>) -> Self {
+        // QStash sometimes returns an empty cursor instead of omitting it on the last page.
+        let cursor = cursor.filter(|cursor| !cursor.is_empty());
+        Page { items, cursor }
+    }
+
+    pub fn has_next_page(&self) -> bool {
+        self.cursor.is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.items.iter()
+    }
+}
+
+impl<T> Default for Page<T> {
+    fn default() -> Self {
+        Page::new(Vec::new(), None)
+    }
+}
+
+/// Endpoints that return everything at once, such as queues, schedules and URL groups,
+/// map to a single page without a cursor.
+impl<T> From<Vec<T>> for Page<T> {
+    fn from(items: Vec<T>) -> Self {
+        Page::new(items, None)
+    }
+}
+
+impl From<EventsResponse> for Page<Event> {
+    fn from(response: EventsResponse) -> Self {
+        Page::new(response.events, response.cursor)
+    }
+}
+
+impl From<DLQMessagesList> for Page<DLQMessage> {
+    fn from(list: DLQMessagesList) -> Self {
+        Page::new(list.messages, list.cursor)
+    }
+}
+
+impl<T> IntoIterator for Page<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Page<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_iteration() {
+        let page = Page::new(vec![1, 2, 3], Some("next".to_string()));
+        assert!(page.has_next_page());
+        assert_eq!(page.len(), 3);
+        assert_eq!(page.iter().sum::<i32>(), 6);
+
+        let items: Vec<i32> = page.into_iter().collect();
+        assert_eq!(items, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_page_empty_cursor_is_last_page() {
+        let page: Page<i32> = Page::new(Vec::new(), Some(String::new()));
+        assert!(!page.has_next_page());
+        assert!(page.is_empty());
+    }
+
+    #[test]
+    fn test_page_from_events_response() {
+        let response = EventsResponse {
+            cursor: Some("next".to_string()),
+            events: vec![Event {
+                message_id: "msg_123".to_string(),
+                ..Default::default()
+            }],
+        };
+        let page = Page::from(response);
+        assert_eq!(page.cursor, Some("next".to_string()));
+        assert_eq!(page.items[0].message_id, "msg_123");
+    }
+}
@@ -0,0 +1,8 @@
+//! A uniform way to ask whether a resource is paused.
+//!
+//! QStash reports the state as `paused` on queues and as `isPaused` on schedules. Both are
+//! deserialized into a `paused` field, and this trait lets callers check either the same way.
+
+pub trait Paused {
+    fn is_paused(&self) -> bool;
+}
@@ -1,7 +1,14 @@
 use crate::client::QstashClient;
-use crate::errors::QstashError;
+use crate::endpoints;
+use crate::errors::{none_if_not_found, QstashError};
+use crate::paused::Paused;
+use futures::stream::{self, StreamExt};
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Maximum number of upserts `upsert_queues` keeps in flight at once.
+const MAX_CONCURRENT_UPSERTS: usize = 8;
 
 impl QstashClient {
     pub async fn upsert_queue(
@@ -10,24 +17,42 @@ impl QstashClient {
     ) -> Result<(), QstashError> {
         let request = self
             .client
-            .get_request_builder(
-                Method::POST,
-                self.base_url
-                    .join("/v2/queues/")
-                    .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
-            )
+            .get_request_builder(Method::POST, self.endpoint_url(&endpoints::queues())?)
             .json(&upsert_request);
 
         self.client.send_request(request).await?;
         Ok(())
     }
 
+    /// Upserts many queues concurrently, with at most `MAX_CONCURRENT_UPSERTS` requests in flight.
+    /// Every upsert is attempted; failures are collected into `QstashError::BulkOperationFailed`.
+    pub async fn upsert_queues(
+        &self,
+        upsert_requests: Vec<UpsertQueueRequest>,
+    ) -> Result<(), QstashError> {
+        let errors: Vec<(String, QstashError)> = stream::iter(upsert_requests)
+            .map(|upsert_request| async move {
+                let queue_name = upsert_request.queue_name.clone();
+                self.upsert_queue(upsert_request)
+                    .await
+                    .map_err(|err| (queue_name, err))
+            })
+            .buffer_unordered(MAX_CONCURRENT_UPSERTS)
+            .filter_map(|result| async move { result.err() })
+            .collect()
+            .await;
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(QstashError::BulkOperationFailed(errors))
+        }
+    }
+
     pub async fn remove_queue(&self, queue_name: &str) -> Result<(), QstashError> {
         let request = self.client.get_request_builder(
             Method::DELETE,
-            self.base_url
-                .join(&format!("/v2/queues/{}", queue_name))
-                .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
+            self.endpoint_url(&endpoints::queue(queue_name))?,
         );
 
         self.client.send_request(request).await?;
@@ -35,20 +60,11 @@ impl QstashClient {
     }
 
     pub async fn list_queues(&self) -> Result<Vec<Queue>, QstashError> {
-        let request = self.client.get_request_builder(
-            Method::GET,
-            self.base_url
-                .join("/v2/queues/")
-                .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
-        );
-
-        let response = self
+        let request = self
             .client
-            .send_request(request)
-            .await?
-            .json::<Vec<Queue>>()
-            .await
-            .map_err(QstashError::ResponseBodyParseError)?;
+            .get_request_builder(Method::GET, self.endpoint_url(&endpoints::queues())?);
+
+        let response = self.client.send_json::<Vec<Queue>>(request).await?;
 
         Ok(response)
     }
@@ -56,28 +72,57 @@ impl QstashClient {
     pub async fn get_queue(&self, queue_name: &str) -> Result<Queue, QstashError> {
         let request = self.client.get_request_builder(
             Method::GET,
-            self.base_url
-                .join(&format!("/v2/queues/{}/", queue_name))
-                .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
+            self.endpoint_url(&endpoints::queue(queue_name))?,
         );
 
-        let response = self
-            .client
-            .send_request(request)
-            .await?
-            .json::<Queue>()
-            .await
-            .map_err(QstashError::ResponseBodyParseError)?;
+        let response = self.client.send_json::<Queue>(request).await?;
 
         Ok(response)
     }
 
+    /// Checks whether a queue exists. A 404 from QStash is reported as `Ok(false)`, any other
+    /// error is returned as is.
+    pub async fn queue_exists(&self, queue_name: &str) -> Result<bool, QstashError> {
+        let queue = none_if_not_found(self.get_queue(queue_name).await)?;
+        Ok(queue.is_some())
+    }
+
+    /// Estimates how long the queue takes to drain its current lag at the given throughput, in
+    /// messages per second. Returns `None` when the throughput isn't a positive number.
+    pub async fn estimate_drain_time(
+        &self,
+        queue_name: &str,
+        observed_throughput_per_sec: f64,
+    ) -> Result<Option<Duration>, QstashError> {
+        let queue = self.get_queue(queue_name).await?;
+        if !observed_throughput_per_sec.is_finite() || observed_throughput_per_sec <= 0.0 {
+            return Ok(None);
+        }
+
+        let lag = f64::from(queue.lag.max(0));
+        Ok(Some(Duration::from_secs_f64(
+            lag / observed_throughput_per_sec,
+        )))
+    }
+
+    /// Pauses a running queue or resumes a paused one, based on its current state.
+    /// Returns the new paused state.
+    pub async fn toggle_queue_paused(&self, queue_name: &str) -> Result<bool, QstashError> {
+        let queue = self.get_queue(queue_name).await?;
+
+        if queue.paused {
+            self.resume_queue(queue_name).await?;
+        } else {
+            self.pause_queue(queue_name).await?;
+        }
+
+        Ok(!queue.paused)
+    }
+
     pub async fn pause_queue(&self, queue_name: &str) -> Result<(), QstashError> {
         let request = self.client.get_request_builder(
             Method::POST,
-            self.base_url
-                .join(&format!("/v2/queues/{}/pause", queue_name))
-                .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
+            self.endpoint_url(&endpoints::pause_queue(queue_name))?,
         );
 
         self.client.send_request(request).await?;
@@ -87,9 +132,7 @@ impl QstashClient {
     pub async fn resume_queue(&self, queue_name: &str) -> Result<(), QstashError> {
         let request = self.client.get_request_builder(
             Method::POST,
-            self.base_url
-                .join(&format!("/v2/queues/{}/resume", queue_name))
-                .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
+            self.endpoint_url(&endpoints::resume_queue(queue_name))?,
         );
 
         self.client.send_request(request).await?;
@@ -123,6 +166,16 @@ pub struct Queue {
 
     /// The number of unprocessed messages that exist in the queue.
     pub lag: i32,
+
+    /// Whether the queue is paused. Missing from older responses, in which case it is `false`.
+    #[serde(default, alias = "isPaused")]
+    pub paused: bool,
+}
+
+impl Paused for Queue {
+    fn is_paused(&self) -> bool {
+        self.paused
+    }
 }
 
 #[cfg(test)]
@@ -132,9 +185,12 @@ mod tests {
     use client::QstashClient;
     use httpmock::Method::{DELETE, GET, POST};
     use httpmock::MockServer;
+    use paused::Paused;
     use queues::{Queue, UpsertQueueRequest};
     use reqwest::StatusCode;
     use reqwest::Url;
+    use serde_json::json;
+    use std::time::Duration;
 
     #[tokio::test]
     async fn test_upsert_queue_success() {
@@ -299,6 +355,7 @@ mod tests {
                 name: "queue1".to_string(),
                 parallelism: 3,
                 lag: 10,
+                paused: false,
             },
             Queue {
                 created_at: 1625097700,
@@ -306,6 +363,7 @@ mod tests {
                 name: "queue2".to_string(),
                 parallelism: 5,
                 lag: 0,
+                paused: false,
             },
         ];
         let list_mock = server.mock(|when, then| {
@@ -395,10 +453,11 @@ mod tests {
             name: queue_name.to_string(),
             parallelism: 4,
             lag: 20,
+            paused: false,
         };
         let get_mock = server.mock(|when, then| {
             when.method(GET)
-                .path(format!("/v2/queues/{}/", queue_name))
+                .path(format!("/v2/queues/{}", queue_name))
                 .header("Authorization", "Bearer test_api_key");
             then.status(StatusCode::OK.as_u16())
                 .header("Content-Type", "application/json")
@@ -425,7 +484,7 @@ mod tests {
         let queue_name = "test-queue";
         let rate_limit_mock = server.mock(|when, then| {
             when.method(GET)
-                .path(format!("/v2/queues/{}/", queue_name))
+                .path(format!("/v2/queues/{}", queue_name))
                 .header("Authorization", "Bearer test_api_key");
             then.status(StatusCode::TOO_MANY_REQUESTS.as_u16())
                 .header("RateLimit-Limit", "1000")
@@ -452,7 +511,7 @@ mod tests {
         let queue_name = "test-queue";
         let invalid_response_mock = server.mock(|when, then| {
             when.method(GET)
-                .path(format!("/v2/queues/{}/", queue_name))
+                .path(format!("/v2/queues/{}", queue_name))
                 .header("Authorization", "Bearer test_api_key");
             then.status(StatusCode::OK.as_u16())
                 .header("Content-Type", "application/json")
@@ -472,6 +531,78 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn test_queue_exists() {
+        let server = MockServer::start();
+        let existing_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/queues/existing-queue")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!({
+                    "createdAt": 1625097600000i64,
+                    "updatedAt": 1625097600000i64,
+                    "name": "existing-queue",
+                    "parallelism": 1,
+                    "lag": 0
+                }));
+        });
+        let missing_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/queues/missing-queue")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::NOT_FOUND.as_u16())
+                .json_body(json!({"error": "queue not found"}));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        assert!(client.queue_exists("existing-queue").await.unwrap());
+        assert!(!client.queue_exists("missing-queue").await.unwrap());
+        existing_mock.assert();
+        missing_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_estimate_drain_time() {
+        let server = MockServer::start();
+        let get_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/queues/test-queue")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!({
+                    "createdAt": 1625097600000i64,
+                    "updatedAt": 1625097600000i64,
+                    "name": "test-queue",
+                    "parallelism": 2,
+                    "lag": 300
+                }));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let estimate = client
+            .estimate_drain_time("test-queue", 12.5)
+            .await
+            .unwrap();
+        assert_eq!(estimate, Some(Duration::from_secs(24)));
+
+        let estimate = client.estimate_drain_time("test-queue", 0.0).await.unwrap();
+        assert_eq!(estimate, None);
+        get_mock.assert_hits(2);
+    }
+
     #[tokio::test]
     async fn test_pause_queue_success() {
         let server = MockServer::start();
@@ -613,4 +744,200 @@ mod tests {
         invalid_response_mock.assert();
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_upsert_queues_success() {
+        let server = MockServer::start();
+        let upsert_mocks: Vec<_> = (1..=3)
+            .map(|i| {
+                server.mock(|when, then| {
+                    when.method(POST)
+                        .path("/v2/queues/")
+                        .header("Authorization", "Bearer test_api_key")
+                        .json_body(
+                            json!({ "queueName": format!("queue-{}", i), "parallelism": i }),
+                        );
+                    then.status(StatusCode::OK.as_u16());
+                })
+            })
+            .collect();
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let upsert_requests = (1..=3)
+            .map(|i| UpsertQueueRequest {
+                queue_name: format!("queue-{}", i),
+                parallelism: i,
+            })
+            .collect();
+        let result = client.upsert_queues(upsert_requests).await;
+        for upsert_mock in &upsert_mocks {
+            upsert_mock.assert();
+        }
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_upsert_queues_aggregates_errors() {
+        let server = MockServer::start();
+        let ok_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/queues/")
+                .json_body(json!({ "queueName": "queue-ok", "parallelism": 1 }));
+            then.status(StatusCode::OK.as_u16());
+        });
+        let failing_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/queues/")
+                .json_body(json!({ "queueName": "queue-bad", "parallelism": 1 }));
+            then.status(StatusCode::BAD_REQUEST.as_u16());
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let upsert_requests = vec![
+            UpsertQueueRequest {
+                queue_name: "queue-ok".to_string(),
+                parallelism: 1,
+            },
+            UpsertQueueRequest {
+                queue_name: "queue-bad".to_string(),
+                parallelism: 1,
+            },
+        ];
+        let result = client.upsert_queues(upsert_requests).await;
+        ok_mock.assert();
+        failing_mock.assert();
+        match result {
+            Err(QstashError::BulkOperationFailed(errors)) => {
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].0, "queue-bad");
+                assert!(matches!(errors[0].1, QstashError::RequestFailed(_)));
+            }
+            _ => panic!("Expected BulkOperationFailed"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_toggle_queue_paused_pauses_running_queue() {
+        let server = MockServer::start();
+        let queue_name = "test-queue";
+        let get_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/v2/queues/{}", queue_name))
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!({
+                    "createdAt": 1625097600,
+                    "updatedAt": 1625097600,
+                    "name": queue_name,
+                    "parallelism": 1,
+                    "lag": 0,
+                    "paused": false
+                }));
+        });
+        let pause_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path(format!("/v2/queues/{}/pause", queue_name))
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16());
+        });
+        let resume_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path(format!("/v2/queues/{}/resume", queue_name));
+            then.status(StatusCode::OK.as_u16());
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let result = client.toggle_queue_paused(queue_name).await;
+        get_mock.assert();
+        pause_mock.assert();
+        resume_mock.assert_hits(0);
+        assert!(result.unwrap());
+    }
+
+    #[test]
+    fn test_queue_deserializes_paused() {
+        let queue = |extra: serde_json::Value| -> Queue {
+            let mut value = json!({
+                "createdAt": 1625097600000i64,
+                "updatedAt": 1625097600000i64,
+                "name": "test-queue",
+                "parallelism": 1,
+                "lag": 0
+            });
+            value
+                .as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            serde_json::from_value(value).unwrap()
+        };
+
+        assert!(queue(json!({"paused": true})).is_paused());
+        assert!(!queue(json!({"paused": false})).is_paused());
+        assert!(!queue(json!({})).is_paused());
+    }
+
+    #[tokio::test]
+    async fn test_get_queue_strict_deserialization_rejects_unknown_fields() {
+        let server = MockServer::start();
+        let queue_name = "test-queue";
+        let get_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/v2/queues/{}", queue_name))
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!({
+                    "createdAt": 1625097600,
+                    "updatedAt": 1625097600,
+                    "name": queue_name,
+                    "parallelism": 4,
+                    "lag": 20,
+                    "paused": false,
+                    "region": "eu-central-1"
+                }));
+        });
+        let lenient_client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let strict_client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .strict_deserialization(true)
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let lenient = lenient_client.get_queue(queue_name).await;
+        let strict = strict_client.get_queue(queue_name).await;
+
+        get_mock.assert_hits(2);
+        assert_eq!(lenient.unwrap().name, queue_name);
+        match strict {
+            Err(err @ QstashError::StrictResponseParseError(_)) => {
+                assert!(
+                    err.to_string().contains("region"),
+                    "unexpected error: {}",
+                    err
+                );
+                assert!(std::error::Error::source(&err).is_some());
+            }
+            other => panic!("expected StrictResponseParseError, got {:?}", other),
+        }
+    }
 }
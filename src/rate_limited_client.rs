@@ -1,33 +1,243 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use reqwest::{header::HeaderMap, Client, Method, RequestBuilder, Response, StatusCode, Url};
+use serde::de::DeserializeOwned;
 
+use crate::client::USER_AGENT;
 use crate::errors::QstashError;
 
+/// Delay before the first retry of a failed request, doubled on every further retry.
+const RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Longest wait for a rate limit window to reset, unless configured otherwise.
+const DEFAULT_MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// Reset values within this many seconds of the current time are read as Unix timestamps,
+/// smaller ones as a relative duration in milliseconds.
+const RESET_TIMESTAMP_TOLERANCE_SECS: u64 = 24 * 60 * 60;
+
+/// Turns the status and body of a failed response into an error more specific than
+/// `QstashError::RequestFailed`, or `None` to keep that one.
+pub(crate) type ErrorBodyParser = fn(StatusCode, &[u8]) -> Option<QstashError>;
+
 /// Struct for handling rate-limited requests.
+///
+/// Cloning is cheap: clones share the connection pool of the underlying `reqwest::Client`
+/// and the last rate limit and attempt count.
+#[derive(Clone)]
 pub struct RateLimitedClient {
     http_client: Client,
-    api_key: String,
+    api_key: Arc<str>,
+    max_retries: u32,
+    strict_deserialization: bool,
+    wait_on_rate_limit: bool,
+    max_rate_limit_wait: Duration,
+    last_rate_limit: Arc<Mutex<Option<RateLimitStatus>>>,
+    last_attempt_count: Arc<Mutex<Option<u32>>>,
+}
+
+/// Quota reported by the rate limit headers of a response. A field is `None` when its header
+/// is missing or isn't a number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitStatus {
+    /// Maximum number of requests allowed in the current window.
+    pub limit: Option<u64>,
+    /// Number of requests left in the current window.
+    pub remaining: Option<u64>,
+    /// When the current window resets, as reported by QStash.
+    pub reset: Option<u64>,
 }
 
 impl RateLimitedClient {
     pub fn new(api_key: String) -> Self {
+        let http_client = Client::builder()
+            .user_agent(USER_AGENT)
+            .build()
+            .unwrap_or_default();
+        RateLimitedClient::with_http_client(api_key, http_client)
+    }
+
+    pub fn with_http_client(api_key: String, http_client: Client) -> Self {
         RateLimitedClient {
-            http_client: Client::new(),
-            api_key,
+            http_client,
+            api_key: api_key.into(),
+            max_retries: 0,
+            strict_deserialization: false,
+            wait_on_rate_limit: false,
+            max_rate_limit_wait: DEFAULT_MAX_RATE_LIMIT_WAIT,
+            last_rate_limit: Arc::new(Mutex::new(None)),
+            last_attempt_count: Arc::new(Mutex::new(None)),
         }
     }
 
+    /// Retries requests that fail to connect or get a server error up to `max_retries` times.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Rejects response bodies with fields the response types don't know about.
+    pub fn with_strict_deserialization(mut self, strict_deserialization: bool) -> Self {
+        self.strict_deserialization = strict_deserialization;
+        self
+    }
+
+    /// Waits for the daily or burst rate limit window to reset and retries once, instead of
+    /// returning the rate limit error right away.
+    pub fn with_wait_on_rate_limit(mut self, wait_on_rate_limit: bool) -> Self {
+        self.wait_on_rate_limit = wait_on_rate_limit;
+        self
+    }
+
+    /// Caps how long `with_wait_on_rate_limit` waits for a reset. Defaults to 60 seconds.
+    pub fn with_max_rate_limit_wait(mut self, max_rate_limit_wait: Duration) -> Self {
+        self.max_rate_limit_wait = max_rate_limit_wait;
+        self
+    }
+
+    /// The rate limit status of the most recent response that carried rate limit headers.
+    pub fn last_rate_limit(&self) -> Option<RateLimitStatus> {
+        *self.last_rate_limit.lock().unwrap()
+    }
+
+    /// How many attempts the most recently completed request took, retries included.
+    pub fn last_attempt_count(&self) -> Option<u32> {
+        *self.last_attempt_count.lock().unwrap()
+    }
+
     pub fn get_request_builder(&self, method: Method, url: Url) -> RequestBuilder {
         self.http_client.request(method, url)
     }
 
-    /// Sends a request and returns immediately on any rate limit or error without retrying.
+    /// Sends a request, retrying connection failures and server errors up to `max_retries`
+    /// times with exponential backoff. With `wait_on_rate_limit`, a daily or burst rate limit
+    /// is waited out once before retrying. Other errors are returned immediately, as are
+    /// failures of requests whose body is a stream and can't be sent twice.
     pub async fn send_request(&self, request: RequestBuilder) -> Result<Response, QstashError> {
+        self.send_request_with(request, None).await
+    }
+
+    /// Same as `send_request`, but the body of an error response other than a rate limit is
+    /// handed to `parse_error_body`, for endpoints that describe their failures in it.
+    pub(crate) async fn send_request_with_error_parser(
+        &self,
+        request: RequestBuilder,
+        parse_error_body: ErrorBodyParser,
+    ) -> Result<Response, QstashError> {
+        self.send_request_with(request, Some(parse_error_body))
+            .await
+    }
+
+    async fn send_request_with(
+        &self,
+        request: RequestBuilder,
+        parse_error_body: Option<ErrorBodyParser>,
+    ) -> Result<Response, QstashError> {
+        let mut request = request;
+        let mut attempt = 1;
+        let mut retries = 0;
+        let mut waited_on_rate_limit = false;
+
+        loop {
+            let can_wait_on_rate_limit = self.wait_on_rate_limit && !waited_on_rate_limit;
+            let retry = if retries < self.max_retries || can_wait_on_rate_limit {
+                request.try_clone()
+            } else {
+                None
+            };
+
+            let result = self.send_once(request, parse_error_body).await;
+            match (result, retry) {
+                (Err(err), Some(retry)) if retries < self.max_retries && is_retryable(&err) => {
+                    tokio::time::sleep(RETRY_BACKOFF * 2u32.pow(retries)).await;
+                    request = retry;
+                    attempt += 1;
+                    retries += 1;
+                }
+                (Err(err), Some(retry))
+                    if can_wait_on_rate_limit && rate_limit_reset(&err).is_some() =>
+                {
+                    self.wait_for_rate_limit_reset(&err).await;
+                    request = retry;
+                    attempt += 1;
+                    waited_on_rate_limit = true;
+                }
+                (result, _) => {
+                    *self.last_attempt_count.lock().unwrap() = Some(attempt);
+                    return result;
+                }
+            }
+        }
+    }
+
+    /// Sleeps until the window of a daily or burst rate limit error resets, for at most
+    /// `max_rate_limit_wait`. Returns `false` right away for any other error.
+    pub(crate) async fn wait_for_rate_limit_reset(&self, err: &QstashError) -> bool {
+        let Some(reset) = rate_limit_reset(err) else {
+            return false;
+        };
+        let wait = rate_limit_wait(reset, unix_now_secs());
+        tokio::time::sleep(wait.min(self.max_rate_limit_wait)).await;
+        true
+    }
+
+    /// Sends a request and parses its JSON response body.
+    pub(crate) async fn send_json<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<T, QstashError> {
+        let response = self.send_request(request).await?;
+        self.parse_json(response).await
+    }
+
+    /// Parses a JSON response body, failing on unknown fields when strict deserialization is on.
+    pub(crate) async fn parse_json<T: DeserializeOwned>(
+        &self,
+        response: Response,
+    ) -> Result<T, QstashError> {
+        if !self.strict_deserialization {
+            return response
+                .json::<T>()
+                .await
+                .map_err(QstashError::ResponseBodyParseError);
+        }
+
+        let body = response
+            .bytes()
+            .await
+            .map_err(QstashError::ResponseBodyParseError)?;
+        let mut unknown_fields = Vec::new();
+        let value =
+            serde_ignored::deserialize(&mut serde_json::Deserializer::from_slice(&body), |path| {
+                unknown_fields.push(path.to_string())
+            })
+            .map_err(QstashError::StrictResponseParseError)?;
+
+        if unknown_fields.is_empty() {
+            Ok(value)
+        } else {
+            Err(QstashError::StrictResponseParseError(
+                serde::de::Error::custom(format!("unknown fields: {}", unknown_fields.join(", "))),
+            ))
+        }
+    }
+
+    async fn send_once(
+        &self,
+        request: RequestBuilder,
+        parse_error_body: Option<ErrorBodyParser>,
+    ) -> Result<Response, QstashError> {
         let response = request
             .header("Authorization", format!("Bearer {}", self.api_key))
             .send()
             .await
             .map_err(QstashError::RequestFailed)?;
 
+        if let Some(status) = parse_rate_limit_status(response.headers()) {
+            *self.last_rate_limit.lock().unwrap() = Some(status);
+        }
+
         // Check if the response has an error status and handle rate limits.
         if let Err(err) = response.error_for_status_ref() {
             if let Some(status) = err.status() {
@@ -37,7 +247,14 @@ impl RateLimitedClient {
                 }
             }
 
-            println!("{:?}", response.bytes().await.unwrap());
+            if let Some(parse_error_body) = parse_error_body {
+                let status = response.status();
+                let body = response.bytes().await.unwrap_or_default();
+                if let Some(error) = parse_error_body(status, &body) {
+                    return Err(error);
+                }
+            }
+
             return Err(QstashError::RequestFailed(err));
         }
 
@@ -45,36 +262,105 @@ impl RateLimitedClient {
     }
 }
 
+// Connection failures, timeouts and 5xx responses are worth another attempt.
+fn is_retryable(err: &QstashError) -> bool {
+    match err {
+        QstashError::RequestFailed(err) => match err.status() {
+            Some(status) => status.is_server_error(),
+            None => err.is_connect() || err.is_timeout(),
+        },
+        QstashError::LlmError { status, .. } => *status >= 500,
+        _ => false,
+    }
+}
+
+// The reset of a daily or burst rate limit error, the ones a wait can clear.
+fn rate_limit_reset(err: &QstashError) -> Option<u64> {
+    match err {
+        QstashError::DailyRateLimitExceeded { reset }
+        | QstashError::BurstRateLimitExceeded { reset } => Some(*reset),
+        _ => None,
+    }
+}
+
+// How long to wait for a rate limit `reset`. Depending on the endpoint it's either a Unix
+// timestamp in seconds or a duration in milliseconds, told apart by how close it is to `now`.
+fn rate_limit_wait(reset: u64, now: u64) -> Duration {
+    if reset >= now.saturating_sub(RESET_TIMESTAMP_TOLERANCE_SECS) {
+        Duration::from_secs(reset.saturating_sub(now))
+    } else {
+        Duration::from_millis(reset)
+    }
+}
+
+fn unix_now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
 /// Parses the response headers to determine which rate limit was exceeded.
 pub fn handle_rate_limit_error(response: &Response) -> QstashError {
     let headers = response.headers();
 
     if headers.contains_key("RateLimit-Limit") {
         // Daily Rate Limit Exceeded
-        let reset = parse_reset_time(headers, "RateLimit-Reset");
+        let reset = parse_u64_header(headers, "RateLimit-Reset").unwrap_or_default();
         return QstashError::DailyRateLimitExceeded { reset };
     } else if headers.contains_key("Burst-RateLimit-Limit") {
         // Burst Rate Limit Exceeded
-        let reset = parse_reset_time(headers, "Burst-RateLimit-Reset");
+        let reset = parse_u64_header(headers, "Burst-RateLimit-Reset").unwrap_or_default();
         return QstashError::BurstRateLimitExceeded { reset };
     } else if headers.contains_key("x-ratelimit-limit-requests") {
         // Chat-based Rate Limit Exceeded
-        let reset_requests = parse_reset_time(headers, "x-ratelimit-reset-requests");
-        let reset_tokens = parse_reset_time(headers, "x-ratelimit-reset-tokens");
+        let reset_requests =
+            parse_u64_header(headers, "x-ratelimit-reset-requests").unwrap_or_default();
+        let reset_tokens =
+            parse_u64_header(headers, "x-ratelimit-reset-tokens").unwrap_or_default();
+        let remaining_requests =
+            parse_u64_header(headers, "x-ratelimit-remaining-requests").unwrap_or_default();
+        let remaining_tokens =
+            parse_u64_header(headers, "x-ratelimit-remaining-tokens").unwrap_or_default();
         return QstashError::ChatRateLimitExceeded {
             reset_requests,
             reset_tokens,
+            remaining_requests,
+            remaining_tokens,
         };
     }
     QstashError::UnspecifiedRateLimitExceeded
 }
 
-fn parse_reset_time(headers: &HeaderMap, header_name: &str) -> u64 {
+// Reads the daily, burst or chat rate limit headers, whichever the response carries.
+fn parse_rate_limit_status(headers: &HeaderMap) -> Option<RateLimitStatus> {
+    [
+        ("RateLimit-Limit", "RateLimit-Remaining", "RateLimit-Reset"),
+        (
+            "Burst-RateLimit-Limit",
+            "Burst-RateLimit-Remaining",
+            "Burst-RateLimit-Reset",
+        ),
+        (
+            "x-ratelimit-limit-requests",
+            "x-ratelimit-remaining-requests",
+            "x-ratelimit-reset-requests",
+        ),
+    ]
+    .into_iter()
+    .find(|(limit, _, _)| headers.contains_key(*limit))
+    .map(|(limit, remaining, reset)| RateLimitStatus {
+        limit: parse_u64_header(headers, limit),
+        remaining: parse_u64_header(headers, remaining),
+        reset: parse_u64_header(headers, reset),
+    })
+}
+
+fn parse_u64_header(headers: &HeaderMap, header_name: &str) -> Option<u64> {
     headers
         .get(header_name)
         .and_then(|value| value.to_str().ok())
         .and_then(|s| s.parse::<u64>().ok())
-        .unwrap_or(0)
 }
 
 #[cfg(test)]
@@ -165,7 +451,9 @@ mod tests {
             then.status(StatusCode::TOO_MANY_REQUESTS.as_u16())
                 .header("x-ratelimit-limit-requests", "100")
                 .header("x-ratelimit-reset-requests", "30")
-                .header("x-ratelimit-reset-tokens", "45");
+                .header("x-ratelimit-reset-tokens", "45")
+                .header("x-ratelimit-remaining-requests", "0")
+                .header("x-ratelimit-remaining-tokens", "1200");
         });
 
         let client = RateLimitedClient::new("test_api_key".to_string());
@@ -180,9 +468,13 @@ mod tests {
             Err(QstashError::ChatRateLimitExceeded {
                 reset_requests,
                 reset_tokens,
+                remaining_requests,
+                remaining_tokens,
             }) => {
                 assert_eq!(reset_requests, 30);
                 assert_eq!(reset_tokens, 45);
+                assert_eq!(remaining_requests, 0);
+                assert_eq!(remaining_tokens, 1200);
             }
             _ => panic!("Expected ChatRateLimitExceeded error"),
         }
@@ -212,4 +504,237 @@ mod tests {
         }
         mock.assert();
     }
+
+    #[tokio::test]
+    async fn test_send_request_updates_last_rate_limit() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/test");
+            then.status(StatusCode::OK.as_u16())
+                .header("RateLimit-Limit", "1000")
+                .header("RateLimit-Remaining", "998")
+                .header("RateLimit-Reset", "3600");
+        });
+
+        let client = RateLimitedClient::new("test_api_key".to_string());
+        let url = Url::parse(&format!("{}/test", &server.base_url())).unwrap();
+        assert_eq!(client.last_rate_limit(), None);
+
+        // Act
+        let request_builder = client.get_request_builder(Method::GET, url);
+        let result = client.send_request(request_builder).await;
+
+        // Assert
+        assert!(result.is_ok());
+        assert_eq!(
+            client.last_rate_limit(),
+            Some(RateLimitStatus {
+                limit: Some(1000),
+                remaining: Some(998),
+                reset: Some(3600),
+            })
+        );
+        mock.assert();
+    }
+
+    // Serves one connection per `(status, headers)` pair, in order, each answered with an
+    // empty body. httpmock can't answer the same request differently over time.
+    async fn serve_in_sequence(
+        responses: Vec<(&'static str, String)>,
+    ) -> (std::net::SocketAddr, tokio::task::JoinHandle<()>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            for (status, headers) in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let read = stream.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                }
+                let response = format!(
+                    "HTTP/1.1 {}\r\n{}Content-Length: 0\r\nConnection: close\r\n\r\n",
+                    status, headers
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (address, server)
+    }
+
+    #[tokio::test]
+    async fn test_send_request_retries_server_errors() {
+        // Arrange: answers the first two requests with a 500, then with a 200.
+        let (address, server) = serve_in_sequence(vec![
+            ("500 Internal Server Error", String::new()),
+            ("500 Internal Server Error", String::new()),
+            ("200 OK", String::new()),
+        ])
+        .await;
+
+        let client = RateLimitedClient::new("test_api_key".to_string()).with_max_retries(3);
+        let url = Url::parse(&format!("http://{}/test", address)).unwrap();
+        let request_builder = client.get_request_builder(Method::GET, url);
+        assert_eq!(client.last_attempt_count(), None);
+
+        // Act
+        let result = client.send_request(request_builder).await;
+
+        // Assert
+        assert!(result.is_ok());
+        assert_eq!(client.last_attempt_count(), Some(3));
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_send_request_does_not_retry_by_default() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/test");
+            then.status(StatusCode::INTERNAL_SERVER_ERROR.as_u16());
+        });
+
+        let client = RateLimitedClient::new("test_api_key".to_string());
+        let url = Url::parse(&format!("{}/test", &server.base_url())).unwrap();
+        let request_builder = client.get_request_builder(Method::GET, url);
+
+        // Act
+        let result = client.send_request(request_builder).await;
+
+        // Assert
+        assert!(matches!(result, Err(QstashError::RequestFailed(_))));
+        assert_eq!(client.last_attempt_count(), Some(1));
+        mock.assert_hits(1);
+    }
+
+    #[test]
+    fn test_rate_limit_wait_formats() {
+        let now = 1_700_000_000;
+        // Unix timestamps in seconds.
+        assert_eq!(rate_limit_wait(now + 30, now), Duration::from_secs(30));
+        assert_eq!(rate_limit_wait(now - 5, now), Duration::ZERO);
+        // Relative durations in milliseconds.
+        assert_eq!(rate_limit_wait(1500, now), Duration::from_millis(1500));
+        assert_eq!(rate_limit_wait(0, now), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_send_request_waits_on_rate_limit_milliseconds_reset() {
+        // Arrange: a daily rate limit resetting in 200 milliseconds, then a success.
+        let (address, server) = serve_in_sequence(vec![
+            (
+                "429 Too Many Requests",
+                "RateLimit-Limit: 1000\r\nRateLimit-Reset: 200\r\n".to_string(),
+            ),
+            ("200 OK", String::new()),
+        ])
+        .await;
+        let client =
+            RateLimitedClient::new("test_api_key".to_string()).with_wait_on_rate_limit(true);
+        let url = Url::parse(&format!("http://{}/test", address)).unwrap();
+        let started = std::time::Instant::now();
+
+        // Act
+        let result = client
+            .send_request(client.get_request_builder(Method::GET, url))
+            .await;
+
+        // Assert
+        assert!(result.is_ok());
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        assert_eq!(client.last_attempt_count(), Some(2));
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_send_request_waits_on_rate_limit_seconds_reset() {
+        // Arrange: a burst rate limit resetting at the next second, then a success.
+        let reset = unix_now_secs() + 1;
+        let (address, server) = serve_in_sequence(vec![
+            (
+                "429 Too Many Requests",
+                format!(
+                    "Burst-RateLimit-Limit: 100\r\nBurst-RateLimit-Reset: {}\r\n",
+                    reset
+                ),
+            ),
+            ("200 OK", String::new()),
+        ])
+        .await;
+        let client =
+            RateLimitedClient::new("test_api_key".to_string()).with_wait_on_rate_limit(true);
+        let url = Url::parse(&format!("http://{}/test", address)).unwrap();
+
+        // Act
+        let result = client
+            .send_request(client.get_request_builder(Method::GET, url))
+            .await;
+
+        // Assert
+        assert!(result.is_ok());
+        assert!(unix_now_secs() >= reset);
+        assert_eq!(client.last_attempt_count(), Some(2));
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_send_request_caps_rate_limit_wait() {
+        // Arrange: a reset an hour away, answered twice with the rate limit.
+        let reset = unix_now_secs() + 3600;
+        let rate_limited = (
+            "429 Too Many Requests",
+            format!("RateLimit-Limit: 1000\r\nRateLimit-Reset: {}\r\n", reset),
+        );
+        let (address, server) = serve_in_sequence(vec![rate_limited.clone(), rate_limited]).await;
+        let client = RateLimitedClient::new("test_api_key".to_string())
+            .with_wait_on_rate_limit(true)
+            .with_max_rate_limit_wait(Duration::from_millis(50));
+        let url = Url::parse(&format!("http://{}/test", address)).unwrap();
+        let started = std::time::Instant::now();
+
+        // Act
+        let result = client
+            .send_request(client.get_request_builder(Method::GET, url))
+            .await;
+
+        // Assert: retried once after the capped wait, then gave up.
+        assert!(matches!(
+            result,
+            Err(QstashError::DailyRateLimitExceeded { reset: r }) if r == reset
+        ));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(client.last_attempt_count(), Some(2));
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_send_request_keeps_error_bodies_unparsed() {
+        // Arrange: an OpenAI-style error body, which only the LLM endpoint parses.
+        let server = MockServer::start_async().await;
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/test");
+            then.status(StatusCode::NOT_FOUND.as_u16())
+                .body(r#"{"error":{"message":"not found"}}"#);
+        });
+
+        let client = RateLimitedClient::new("test_api_key".to_string());
+        let url = Url::parse(&format!("{}/test", &server.base_url())).unwrap();
+        let request_builder = client.get_request_builder(Method::GET, url);
+
+        // Act
+        let result = client.send_request(request_builder).await;
+
+        // Assert
+        match result {
+            Err(QstashError::RequestFailed(err)) => {
+                assert_eq!(err.status(), Some(StatusCode::NOT_FOUND))
+            }
+            other => panic!("Expected RequestFailed, got {:?}", other.map(|_| ())),
+        }
+        mock.assert_hits(1);
+    }
 }
@@ -1,11 +1,21 @@
 use std::collections::HashMap;
+use std::time::Duration;
 
-use reqwest::header::HeaderMap;
+use chrono::{DateTime, Utc};
+
+use futures::stream::{self, StreamExt};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
 
 use crate::client::QstashClient;
+use crate::endpoints;
 use crate::errors::QstashError;
+use crate::message_types::deserialize_id;
+use crate::paused::Paused;
+
+/// Maximum number of fetches `get_schedules` keeps in flight at once.
+const MAX_CONCURRENT_FETCHES: usize = 8;
 
 impl QstashClient {
     pub async fn create_schedule(
@@ -18,68 +28,146 @@ impl QstashClient {
             .client
             .get_request_builder(
                 Method::POST,
-                self.base_url
-                    .join(&format!("/v2/schedules/{}", destination))
-                    .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
+                self.endpoint_url(&endpoints::schedule(destination))?,
             )
             .headers(headers)
             .body(body);
 
         let response = self
             .client
-            .send_request(request)
-            .await?
-            .json::<CreateScheduleResponse>()
-            .await
-            .map_err(QstashError::ResponseBodyParseError)?;
+            .send_json::<CreateScheduleResponse>(request)
+            .await?;
 
         Ok(response)
     }
 
+    /// Creates a schedule with the `Upstash-*` headers built from `options`.
+    /// Fails with `QstashError::MissingCron` before sending anything if no cron expression is set.
+    pub async fn create_schedule_with_options(
+        &self,
+        destination: &str,
+        options: ScheduleOptions,
+        body: Vec<u8>,
+    ) -> Result<CreateScheduleResponse, QstashError> {
+        let headers = options.to_headers()?;
+        self.create_schedule(destination, headers, body).await
+    }
+
+    /// Creates a schedule under `schedule_id` instead of a generated id, by sending it as the
+    /// `Upstash-Schedule-Id` header. Creating a schedule with an id that already exists updates
+    /// that schedule, which makes provisioning repeatable. Returns the id QStash echoes back.
+    pub async fn create_schedule_with_id(
+        &self,
+        schedule_id: &str,
+        destination: &str,
+        mut headers: HeaderMap,
+        body: Vec<u8>,
+    ) -> Result<CreateScheduleResponse, QstashError> {
+        let schedule_id = HeaderValue::from_str(schedule_id)
+            .map_err(|e| QstashError::InvalidHeader(e.to_string()))?;
+        headers.insert("Upstash-Schedule-Id", schedule_id);
+        self.create_schedule(destination, headers, body).await
+    }
+
+    /// Creates a schedule unless one with the same destination and `Upstash-Cron` header already
+    /// exists, in which case the id of the existing schedule is returned instead.
+    ///
+    /// The check and the creation are separate requests, so concurrent callers can still
+    /// create duplicates.
+    pub async fn create_schedule_if_absent(
+        &self,
+        destination: &str,
+        headers: HeaderMap,
+        body: Vec<u8>,
+    ) -> Result<CreateScheduleResponse, QstashError> {
+        let cron = headers
+            .get("Upstash-Cron")
+            .and_then(|value| value.to_str().ok());
+        let existing = self.list_schedules().await?.into_iter().find(|schedule| {
+            schedule.destination == destination && Some(schedule.cron.as_str()) == cron
+        });
+
+        match existing {
+            Some(schedule) => Ok(CreateScheduleResponse {
+                schedule_id: schedule.id,
+            }),
+            None => self.create_schedule(destination, headers, body).await,
+        }
+    }
+
+    /// Creates a schedule like `create_schedule`, then fetches it so the settings QStash
+    /// persisted (retries, delay, method, ...) are returned.
+    pub async fn create_schedule_and_fetch(
+        &self,
+        destination: &str,
+        headers: HeaderMap,
+        body: Vec<u8>,
+    ) -> Result<Schedule, QstashError> {
+        let response = self.create_schedule(destination, headers, body).await?;
+        self.get_schedule(&response.schedule_id).await
+    }
+
     pub async fn get_schedule(&self, schedule_id: &str) -> Result<Schedule, QstashError> {
         let request = self.client.get_request_builder(
             Method::GET,
-            self.base_url
-                .join(&format!("/v2/schedules/{}", schedule_id))
-                .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
+            self.endpoint_url(&endpoints::schedule(schedule_id))?,
         );
 
-        let response = self
-            .client
-            .send_request(request)
-            .await?
-            .json::<Schedule>()
-            .await
-            .map_err(QstashError::ResponseBodyParseError)?;
+        let response = self.client.send_json::<Schedule>(request).await?;
 
         Ok(response)
     }
 
+    /// Fetches many schedules concurrently, with at most `MAX_CONCURRENT_FETCHES` requests in
+    /// flight. Results are returned in the order of `schedule_ids`, one per id, so a missing
+    /// schedule doesn't fail the others and the schedules that were found aren't lost.
+    pub async fn get_schedules(
+        &self,
+        schedule_ids: &[String],
+    ) -> Vec<Result<Schedule, QstashError>> {
+        stream::iter(schedule_ids)
+            .map(|schedule_id| self.get_schedule(schedule_id))
+            .buffered(MAX_CONCURRENT_FETCHES)
+            .collect()
+            .await
+    }
+
     pub async fn list_schedules(&self) -> Result<Vec<Schedule>, QstashError> {
-        let request = self.client.get_request_builder(
-            Method::GET,
-            self.base_url
-                .join("/v2/schedules")
-                .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
-        );
+        let request = self
+            .client
+            .get_request_builder(Method::GET, self.endpoint_url(&endpoints::schedules())?);
 
         let response = self
             .client
-            .send_request(request)
+            .send_json::<ListSchedulesResponse>(request)
+            .await?;
+
+        Ok(response.into_schedules())
+    }
+
+    /// Lists the schedules together with their next run, soonest first.
+    /// Schedules without a known next run, such as paused ones, come last.
+    pub async fn list_schedules_with_next_run(
+        &self,
+    ) -> Result<Vec<(Schedule, Option<DateTime<Utc>>)>, QstashError> {
+        let mut schedules: Vec<_> = self
+            .list_schedules()
             .await?
-            .json::<Vec<Schedule>>()
-            .await
-            .map_err(QstashError::ResponseBodyParseError)?;
+            .into_iter()
+            .map(|schedule| {
+                let next_run = schedule.next_run();
+                (schedule, next_run)
+            })
+            .collect();
 
-        Ok(response)
+        schedules.sort_by_key(|(_, next_run)| (next_run.is_none(), *next_run));
+        Ok(schedules)
     }
 
     pub async fn remove_schedule(&self, schedule_id: &str) -> Result<(), QstashError> {
         let request = self.client.get_request_builder(
             Method::DELETE,
-            self.base_url
-                .join(&format!("/v2/schedules/{}", schedule_id))
-                .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
+            self.endpoint_url(&endpoints::schedule(schedule_id))?,
         );
 
         self.client.send_request(request).await?;
@@ -90,9 +178,7 @@ impl QstashClient {
     pub async fn pause_schedule(&self, schedule_id: &str) -> Result<(), QstashError> {
         let request = self.client.get_request_builder(
             Method::POST,
-            self.base_url
-                .join(&format!("/v2/schedules/{}/pause", schedule_id))
-                .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
+            self.endpoint_url(&endpoints::pause_schedule(schedule_id))?,
         );
 
         self.client.send_request(request).await?;
@@ -103,9 +189,7 @@ impl QstashClient {
     pub async fn resume_schedule(&self, schedule_id: &str) -> Result<(), QstashError> {
         let request = self.client.get_request_builder(
             Method::POST,
-            self.base_url
-                .join(&format!("/v2/schedules/{}/resume", schedule_id))
-                .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
+            self.endpoint_url(&endpoints::resume_schedule(schedule_id))?,
         );
 
         self.client.send_request(request).await?;
@@ -114,9 +198,26 @@ impl QstashClient {
     }
 }
 
+/// The schedules list, either as a bare array or wrapped in `{"schedules": [...]}`.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ListSchedulesResponse {
+    Bare(Vec<Schedule>),
+    Wrapped { schedules: Vec<Schedule> },
+}
+
+impl ListSchedulesResponse {
+    fn into_schedules(self) -> Vec<Schedule> {
+        match self {
+            ListSchedulesResponse::Bare(schedules) => schedules,
+            ListSchedulesResponse::Wrapped { schedules } => schedules,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateScheduleResponse {
-    #[serde(rename = "scheduleId")]
+    #[serde(rename = "scheduleId", deserialize_with = "deserialize_id")]
     pub schedule_id: String,
 }
 /// Represents a single schedule object within the Response array.
@@ -127,6 +228,7 @@ pub struct Schedule {
     pub created_at: i64,
 
     /// The ID of the schedule.
+    #[serde(deserialize_with = "deserialize_id")]
     pub id: String,
 
     /// The cron expression used to schedule the message.
@@ -155,6 +257,158 @@ pub struct Schedule {
     /// The URL where a callback is sent after the message is delivered.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub callback: Option<String>,
+
+    /// The next time the schedule fires, as reported by QStash. Unix timestamp in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_schedule_time: Option<i64>,
+
+    /// Whether the schedule is paused. QStash reports it as `isPaused`.
+    #[serde(rename = "isPaused", alias = "paused")]
+    pub paused: bool,
+}
+
+impl Paused for Schedule {
+    fn is_paused(&self) -> bool {
+        self.paused
+    }
+}
+
+impl Schedule {
+    /// The next time the schedule fires, if QStash reported it.
+    pub fn next_run(&self) -> Option<DateTime<Utc>> {
+        self.next_schedule_time
+            .and_then(DateTime::from_timestamp_millis)
+    }
+
+    // Rebuilds the headers `create_schedule` needs to recreate this schedule under the same id.
+    pub(crate) fn create_headers(&self) -> Result<HeaderMap, QstashError> {
+        let mut headers = HeaderMap::new();
+
+        for (name, values) in &self.header {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| QstashError::InvalidHeader(e.to_string()))?;
+            for value in values {
+                let value = HeaderValue::from_str(value)
+                    .map_err(|e| QstashError::InvalidHeader(e.to_string()))?;
+                headers.append(name.clone(), value);
+            }
+        }
+
+        let mut insert = |name: &'static str, value: String| -> Result<(), QstashError> {
+            let value = HeaderValue::from_str(&value)
+                .map_err(|e| QstashError::InvalidHeader(e.to_string()))?;
+            headers.insert(name, value);
+            Ok(())
+        };
+
+        insert("Upstash-Cron", self.cron.clone())?;
+        if !self.id.is_empty() {
+            insert("Upstash-Schedule-Id", self.id.clone())?;
+        }
+        if !self.method.is_empty() {
+            insert("Upstash-Method", self.method.clone())?;
+        }
+        if let Some(retries) = self.retries {
+            insert("Upstash-Retries", retries.to_string())?;
+        }
+        if let Some(delay) = self.delay {
+            insert("Upstash-Delay", format!("{}s", delay))?;
+        }
+        if let Some(callback) = &self.callback {
+            insert("Upstash-Callback", callback.clone())?;
+        }
+
+        Ok(headers)
+    }
+}
+
+/// Typed alternative to building the `Upstash-*` schedule headers by hand.
+#[derive(Debug, Clone, Default)]
+pub struct ScheduleOptions {
+    cron: Option<String>,
+    retries: Option<u32>,
+    delay: Option<Duration>,
+    callback: Option<String>,
+    failure_callback: Option<String>,
+    method: Option<Method>,
+}
+
+impl ScheduleOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The cron expression the schedule fires on, sent as `Upstash-Cron`. Required.
+    pub fn cron(mut self, cron: &str) -> Self {
+        self.cron = Some(cron.to_string());
+        self
+    }
+
+    /// How many times QStash retries a failed delivery, sent as `Upstash-Retries`.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = Some(retries);
+        self
+    }
+
+    /// Delays every delivery of the schedule, sent as `Upstash-Delay` in whole seconds.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// URL called with the destination's response after each delivery, sent as
+    /// `Upstash-Callback`.
+    pub fn callback(mut self, url: &str) -> Self {
+        self.callback = Some(url.to_string());
+        self
+    }
+
+    /// URL called once every delivery attempt has failed, sent as `Upstash-Failure-Callback`.
+    pub fn failure_callback(mut self, url: &str) -> Self {
+        self.failure_callback = Some(url.to_string());
+        self
+    }
+
+    /// The HTTP method the messages are delivered with, sent as `Upstash-Method`.
+    pub fn method(mut self, method: Method) -> Self {
+        self.method = Some(method);
+        self
+    }
+
+    pub(crate) fn to_headers(&self) -> Result<HeaderMap, QstashError> {
+        let cron = self
+            .cron
+            .as_deref()
+            .filter(|cron| !cron.trim().is_empty())
+            .ok_or(QstashError::MissingCron)?;
+
+        let mut headers = HeaderMap::new();
+        let mut insert = |name: &'static str, value: &str| -> Result<(), QstashError> {
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| QstashError::InvalidHeader(e.to_string()))?;
+            headers.insert(name, value);
+            Ok(())
+        };
+
+        insert("Upstash-Cron", cron)?;
+        if let Some(retries) = self.retries {
+            insert("Upstash-Retries", &retries.to_string())?;
+        }
+        if let Some(delay) = self.delay {
+            insert("Upstash-Delay", &format!("{}s", delay.as_secs()))?;
+        }
+        if let Some(callback) = &self.callback {
+            insert("Upstash-Callback", callback)?;
+        }
+        if let Some(failure_callback) = &self.failure_callback {
+            insert("Upstash-Failure-Callback", failure_callback)?;
+        }
+        if let Some(method) = &self.method {
+            insert("Upstash-Method", method.as_str())?;
+        }
+
+        Ok(headers)
+    }
 }
 
 #[cfg(test)]
@@ -166,10 +420,12 @@ mod tests {
     use client::QstashClient;
     use httpmock::Method::{DELETE, GET, POST};
     use httpmock::MockServer;
+    use paused::Paused;
     use reqwest::header::HeaderMap;
     use reqwest::StatusCode;
     use reqwest::Url;
-    use schedules::{CreateScheduleResponse, Schedule};
+    use schedules::{CreateScheduleResponse, Schedule, ScheduleOptions};
+    use std::time::Duration;
 
     #[tokio::test]
     async fn test_create_schedule_success() {
@@ -206,6 +462,58 @@ mod tests {
         assert_eq!(response.schedule_id, expected_response.schedule_id);
     }
 
+    #[tokio::test]
+    async fn test_create_schedule_if_absent() {
+        let server = MockServer::start();
+        let list_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/schedules")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!([{
+                    "id": "schedule123",
+                    "cron": "0 0 * * *",
+                    "destination": "https://example.com/destination"
+                }]));
+        });
+        let create_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/schedules/https://example.com/destination")
+                .header("Authorization", "Bearer test_api_key")
+                .header("Upstash-Cron", "30 1 * * *");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!({"scheduleId": "schedule456"}));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let destination = "https://example.com/destination";
+
+        let mut headers = HeaderMap::new();
+        headers.insert("Upstash-Cron", "0 0 * * *".parse().unwrap());
+        let existing = client
+            .create_schedule_if_absent(destination, headers, Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(existing.schedule_id, "schedule123");
+        create_mock.assert_hits(0);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("Upstash-Cron", "30 1 * * *".parse().unwrap());
+        let created = client
+            .create_schedule_if_absent(destination, headers, Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(created.schedule_id, "schedule456");
+        create_mock.assert_hits(1);
+        list_mock.assert_hits(2);
+    }
+
     #[tokio::test]
     async fn test_create_schedule_rate_limit_error() {
         let server = MockServer::start();
@@ -283,6 +591,8 @@ mod tests {
             retries: Some(3),
             delay: Some(60),
             callback: Some("https://example.com/callback".to_string()),
+            next_schedule_time: None,
+            paused: false,
         };
         let get_mock = server.mock(|when, then| {
             when.method(GET)
@@ -384,6 +694,8 @@ mod tests {
                 retries: Some(3),
                 delay: Some(60),
                 callback: Some("https://example.com/callback1".to_string()),
+                next_schedule_time: None,
+                paused: false,
             },
             Schedule {
                 created_at: 1625097700000,
@@ -399,6 +711,8 @@ mod tests {
                 retries: None,
                 delay: Some(120),
                 callback: None,
+                next_schedule_time: None,
+                paused: false,
             },
         ];
         let list_mock = server.mock(|when, then| {
@@ -484,6 +798,37 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn test_list_schedules_accepts_bare_and_wrapped_responses() {
+        let schedules = json!([
+            {"id": "schedule123", "cron": "0 0 * * *"},
+            {"id": "schedule456", "cron": "30 1 * * *"}
+        ]);
+        for body in [schedules.clone(), json!({ "schedules": schedules })] {
+            let server = MockServer::start();
+            let list_mock = server.mock(|when, then| {
+                when.method(GET)
+                    .path("/v2/schedules")
+                    .header("Authorization", "Bearer test_api_key");
+                then.status(StatusCode::OK.as_u16())
+                    .header("Content-Type", "application/json")
+                    .json_body(body.clone());
+            });
+            let client = QstashClient::builder()
+                .base_url(Url::parse(&server.base_url()).unwrap())
+                .unwrap()
+                .api_key("test_api_key")
+                .build()
+                .expect("Failed to build QstashClient");
+
+            let result = client.list_schedules().await.unwrap();
+
+            list_mock.assert();
+            let ids: Vec<&str> = result.iter().map(|s| s.id.as_str()).collect();
+            assert_eq!(ids, vec!["schedule123", "schedule456"], "body: {}", body);
+        }
+    }
+
     #[tokio::test]
     async fn test_remove_schedule_success() {
         let server = MockServer::start();
@@ -627,4 +972,241 @@ mod tests {
             Err(QstashError::DailyRateLimitExceeded { reset: 1625097600 })
         ));
     }
+
+    #[tokio::test]
+    async fn test_list_schedules_with_next_run_sorts_by_soonest() {
+        let server = MockServer::start();
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let list_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/schedules")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!([
+                    {"id": "later", "cron": "0 12 * * *", "nextScheduleTime": 1700000600000i64},
+                    {"id": "paused", "cron": "0 0 * * *"},
+                    {"id": "sooner", "cron": "*/5 * * * *", "nextScheduleTime": 1700000300000i64}
+                ]));
+        });
+
+        let schedules = client.list_schedules_with_next_run().await.unwrap();
+
+        list_mock.assert();
+        let ids: Vec<&str> = schedules.iter().map(|(s, _)| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["sooner", "later", "paused"]);
+        assert_eq!(
+            schedules[0].1.map(|next_run| next_run.timestamp_millis()),
+            Some(1700000300000)
+        );
+        assert!(schedules[2].1.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_schedules_reports_missing_schedule_per_id() {
+        let server = MockServer::start();
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let found_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/schedules/schedule123")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!({"id": "schedule123", "cron": "0 0 * * *"}));
+        });
+        let missing_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/schedules/missing")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::NOT_FOUND.as_u16())
+                .json_body(json!({"error": "schedule not found"}));
+        });
+
+        let results = client
+            .get_schedules(&["schedule123".to_string(), "missing".to_string()])
+            .await;
+
+        found_mock.assert();
+        missing_mock.assert();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap().id, "schedule123");
+        assert!(matches!(results[1], Err(QstashError::RequestFailed(_))));
+    }
+
+    #[test]
+    fn test_schedule_deserializes_is_paused() {
+        let paused: Schedule =
+            serde_json::from_value(json!({"id": "schedule123", "isPaused": true})).unwrap();
+        assert!(paused.is_paused());
+
+        let running: Schedule = serde_json::from_value(json!({"id": "schedule123"})).unwrap();
+        assert!(!running.is_paused());
+    }
+
+    #[tokio::test]
+    async fn test_create_schedule_and_fetch() {
+        let server = MockServer::start();
+        let create_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/schedules/https://example.com/destination")
+                .header("Authorization", "Bearer test_api_key")
+                .header("Upstash-Cron", "0 0 * * *")
+                .header("Upstash-Retries", "2")
+                .header("Upstash-Delay", "30s");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!({"scheduleId": "schedule123"}));
+        });
+        let get_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/schedules/schedule123")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!({
+                    "id": "schedule123",
+                    "cron": "0 0 * * *",
+                    "destination": "https://example.com/destination",
+                    "method": "POST",
+                    "retries": 2,
+                    "delay": 30
+                }));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let mut headers = HeaderMap::new();
+        headers.insert("Upstash-Cron", "0 0 * * *".parse().unwrap());
+        headers.insert("Upstash-Retries", "2".parse().unwrap());
+        headers.insert("Upstash-Delay", "30s".parse().unwrap());
+
+        let schedule = client
+            .create_schedule_and_fetch("https://example.com/destination", headers, Vec::new())
+            .await
+            .unwrap();
+
+        create_mock.assert();
+        get_mock.assert();
+        assert_eq!(schedule.id, "schedule123");
+        assert_eq!(schedule.retries, Some(2));
+        assert_eq!(schedule.delay, Some(30));
+    }
+
+    #[tokio::test]
+    async fn test_create_schedule_with_id() {
+        let server = MockServer::start();
+        let create_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/schedules/https://example.com/destination")
+                .header("Authorization", "Bearer test_api_key")
+                .header("Upstash-Cron", "0 0 * * *")
+                .header("Upstash-Schedule-Id", "nightly-report");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!({"scheduleId": "nightly-report"}));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+        let mut headers = HeaderMap::new();
+        headers.insert("Upstash-Cron", "0 0 * * *".parse().unwrap());
+
+        let response = client
+            .create_schedule_with_id(
+                "nightly-report",
+                "https://example.com/destination",
+                headers,
+                Vec::new(),
+            )
+            .await
+            .unwrap();
+
+        create_mock.assert();
+        assert_eq!(response.schedule_id, "nightly-report");
+    }
+
+    #[test]
+    fn test_schedule_options_to_headers() {
+        let headers = ScheduleOptions::new()
+            .cron("*/5 * * * *")
+            .retries(3)
+            .delay(Duration::from_secs(90))
+            .callback("https://example.com/callback")
+            .failure_callback("https://example.com/failed")
+            .method(reqwest::Method::PUT)
+            .to_headers()
+            .unwrap();
+
+        assert_eq!(headers.get("Upstash-Cron").unwrap(), "*/5 * * * *");
+        assert_eq!(headers.get("Upstash-Retries").unwrap(), "3");
+        assert_eq!(headers.get("Upstash-Delay").unwrap(), "90s");
+        assert_eq!(
+            headers.get("Upstash-Callback").unwrap(),
+            "https://example.com/callback"
+        );
+        assert_eq!(
+            headers.get("Upstash-Failure-Callback").unwrap(),
+            "https://example.com/failed"
+        );
+        assert_eq!(headers.get("Upstash-Method").unwrap(), "PUT");
+        assert_eq!(headers.len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_create_schedule_with_options() {
+        let server = MockServer::start();
+        let create_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/v2/schedules/https://example.com/destination")
+                .header("Authorization", "Bearer test_api_key")
+                .header("Upstash-Cron", "0 0 * * *")
+                .header("Upstash-Retries", "2");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!({"scheduleId": "schedule123"}));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let response = client
+            .create_schedule_with_options(
+                "https://example.com/destination",
+                ScheduleOptions::new().cron("0 0 * * *").retries(2),
+                Vec::new(),
+            )
+            .await
+            .unwrap();
+        create_mock.assert();
+        assert_eq!(response.schedule_id, "schedule123");
+
+        let result = client
+            .create_schedule_with_options(
+                "https://example.com/destination",
+                ScheduleOptions::new().retries(2),
+                Vec::new(),
+            )
+            .await;
+        create_mock.assert_hits(1);
+        assert!(matches!(result, Err(QstashError::MissingCron)));
+    }
 }
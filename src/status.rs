@@ -0,0 +1,131 @@
+use futures::try_join;
+
+use crate::client::QstashClient;
+use crate::dead_letter_queue::DlqQueryParams;
+use crate::errors::QstashError;
+use crate::paused::Paused;
+
+impl QstashClient {
+    /// Checks that the account is reachable in one call, e.g. as a startup self-check.
+    ///
+    /// Fetches the signing keys to verify the credentials, the queues and the DLQ concurrently
+    /// and fails with the first error. The DLQ depth is counted by paging through the whole DLQ,
+    /// so it is capped like `dlq_list_all_messages`.
+    pub async fn status(&self) -> Result<QstashStatus, QstashError> {
+        let (_, queues, dlq_messages) = try_join!(
+            self.get_signing_keys(),
+            self.list_queues(),
+            self.dlq_list_all_messages(DlqQueryParams::default())
+        )?;
+
+        Ok(QstashStatus {
+            queue_count: queues.len(),
+            paused_queue_count: queues.iter().filter(|queue| queue.is_paused()).count(),
+            total_lag: queues.iter().map(|queue| i64::from(queue.lag)).sum(),
+            dlq_depth: dlq_messages.len(),
+        })
+    }
+}
+
+/// A snapshot of the account's health, as returned by `status`.
+#[derive(Debug, Default, PartialEq)]
+pub struct QstashStatus {
+    /// The number of queues.
+    pub queue_count: usize,
+    /// The number of paused queues.
+    pub paused_queue_count: usize,
+    /// The number of unprocessed messages across all queues.
+    pub total_lag: i64,
+    /// The number of messages in the dead letter queue.
+    pub dlq_depth: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::Method::GET;
+    use httpmock::MockServer;
+    use reqwest::StatusCode;
+    use reqwest::Url;
+
+    #[tokio::test]
+    async fn test_status() {
+        let server = MockServer::start();
+        let keys_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/keys")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .json_body(json!({"current": "current_key", "next": "next_key"}));
+        });
+        let queues_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/queues/")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16()).json_body(json!([
+                {"createdAt": 1, "updatedAt": 2, "name": "queue1", "parallelism": 1, "lag": 3},
+                {"createdAt": 1, "updatedAt": 2, "name": "queue2", "parallelism": 1, "lag": 4, "paused": true}
+            ]));
+        });
+        let dlq_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/dlq")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16()).json_body(json!({
+                "messages": [
+                    {"messageId": "msg1", "dlqId": "dlq1"},
+                    {"messageId": "msg2", "dlqId": "dlq2"}
+                ]
+            }));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let status = client.status().await.unwrap();
+
+        keys_mock.assert();
+        queues_mock.assert();
+        dlq_mock.assert();
+        assert_eq!(
+            status,
+            QstashStatus {
+                queue_count: 2,
+                paused_queue_count: 1,
+                total_lag: 7,
+                dlq_depth: 2,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_status_invalid_credentials() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/v2/keys");
+            then.status(StatusCode::UNAUTHORIZED.as_u16());
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/v2/queues/");
+            then.status(StatusCode::OK.as_u16()).json_body(json!([]));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/v2/dlq");
+            then.status(StatusCode::OK.as_u16())
+                .json_body(json!({"messages": []}));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("wrong_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let result = client.status().await;
+
+        assert!(matches!(result, Err(QstashError::RequestFailed(_))));
+    }
+}
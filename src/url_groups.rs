@@ -2,6 +2,7 @@ use reqwest::Method;
 use serde::{Deserialize, Serialize};
 
 use crate::client::QstashClient;
+use crate::endpoints;
 use crate::errors::QstashError;
 
 impl QstashClient {
@@ -10,13 +11,12 @@ impl QstashClient {
         url_group_name: &str,
         endpoints: Vec<Endpoint>,
     ) -> Result<(), QstashError> {
+        validate_url_group_name(url_group_name)?;
         let request = self
             .client
             .get_request_builder(
                 Method::POST,
-                self.base_url
-                    .join(&format!("/v2/topics/{}/endpoints", url_group_name))
-                    .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
+                self.endpoint_url(&endpoints::url_group_endpoints(url_group_name))?,
             )
             .json(&json!({
                 "endpoints": endpoints,
@@ -27,54 +27,71 @@ impl QstashClient {
     }
 
     pub async fn get_url_group(&self, url_group_name: &str) -> Result<UrlGroup, QstashError> {
+        validate_url_group_name(url_group_name)?;
         let request = self.client.get_request_builder(
             Method::GET,
-            self.base_url
-                .join(&format!("/v2/topics/{}", url_group_name))
-                .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
+            self.endpoint_url(&endpoints::url_group(url_group_name))?,
         );
 
-        let response = self
-            .client
-            .send_request(request)
-            .await?
-            .json::<UrlGroup>()
-            .await
-            .map_err(QstashError::ResponseBodyParseError)?;
+        let response = self.client.send_json::<UrlGroup>(request).await?;
 
         Ok(response)
     }
-    pub async fn list_url_groups(&self) -> Result<Vec<UrlGroup>, QstashError> {
-        let request = self.client.get_request_builder(
-            Method::GET,
-            self.base_url
-                .join("/v2/topics")
-                .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
-        );
 
-        let response = self
+    /// Returns the endpoints of the URL group.
+    ///
+    /// QStash has no endpoint listing only the endpoints of a group, so this fetches the group
+    /// like `get_url_group` does.
+    pub async fn get_url_group_endpoints(
+        &self,
+        url_group_name: &str,
+    ) -> Result<Vec<Endpoint>, QstashError> {
+        Ok(self.get_url_group(url_group_name).await?.endpoints)
+    }
+
+    /// Returns the URLs of the endpoints in the URL group, the destinations a message published
+    /// to the group fans out to.
+    pub async fn resolve_url_group(
+        &self,
+        url_group_name: &str,
+    ) -> Result<Vec<String>, QstashError> {
+        let endpoints = self.get_url_group_endpoints(url_group_name).await?;
+
+        Ok(endpoints.into_iter().map(|endpoint| endpoint.url).collect())
+    }
+
+    pub async fn list_url_groups(&self) -> Result<Vec<UrlGroup>, QstashError> {
+        let request = self
             .client
-            .send_request(request)
-            .await?
-            .json::<Vec<UrlGroup>>()
-            .await
-            .map_err(QstashError::ResponseBodyParseError)?;
+            .get_request_builder(Method::GET, self.endpoint_url(&endpoints::url_groups())?);
+
+        let response = self.client.send_json::<Vec<UrlGroup>>(request).await?;
 
         Ok(response)
     }
 
+    // Upserts every endpoint of `url_group`. Groups without endpoints are skipped since QStash
+    // requires at least one endpoint per group.
+    pub(crate) async fn upsert_url_group(&self, url_group: UrlGroup) -> Result<(), QstashError> {
+        if url_group.endpoints.is_empty() {
+            return Ok(());
+        }
+
+        self.upsert_url_group_endpoint(&url_group.name, url_group.endpoints)
+            .await
+    }
+
     pub async fn remove_endpoints(
         &self,
         url_group_name: &str,
         endpoints: Vec<Endpoint>,
     ) -> Result<(), QstashError> {
+        validate_url_group_name(url_group_name)?;
         let request = self
             .client
             .get_request_builder(
                 Method::DELETE,
-                self.base_url
-                    .join(&format!("/v2/topics/{}/endpoints", url_group_name))
-                    .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
+                self.endpoint_url(&endpoints::url_group_endpoints(url_group_name))?,
             )
             .json(&json!({
                 "endpoints": endpoints,
@@ -85,35 +102,89 @@ impl QstashClient {
     }
 
     pub async fn remove_url_group(&self, url_group_name: &str) -> Result<(), QstashError> {
+        validate_url_group_name(url_group_name)?;
         let request = self.client.get_request_builder(
             Method::DELETE,
-            self.base_url
-                .join(&format!("/v2/topics/{}", url_group_name))
-                .map_err(|e| QstashError::InvalidRequestUrl(e.to_string()))?,
+            self.endpoint_url(&endpoints::url_group(url_group_name))?,
         );
 
         self.client.send_request(request).await?;
 
         Ok(())
     }
+
+    /// Removes the schedules whose destination is the URL group, then the URL group itself, so
+    /// no schedule is left pointing at a deleted group. Returns the ids of the removed schedules.
+    ///
+    /// If removing a schedule fails, the URL group is kept and the error is returned.
+    pub async fn remove_url_group_cascade(
+        &self,
+        url_group_name: &str,
+    ) -> Result<Vec<String>, QstashError> {
+        validate_url_group_name(url_group_name)?;
+        let schedule_ids: Vec<String> = self
+            .list_schedules()
+            .await?
+            .into_iter()
+            .filter(|schedule| schedule.destination == url_group_name)
+            .map(|schedule| schedule.id)
+            .collect();
+
+        for schedule_id in &schedule_ids {
+            self.remove_schedule(schedule_id).await?;
+        }
+        self.remove_url_group(url_group_name).await?;
+
+        Ok(schedule_ids)
+    }
 }
 
-#[derive(Default, Serialize, Deserialize, Debug)]
-#[serde(default)]
+/// Checks that `name` is a valid URL group name: non-empty and made only of alphanumeric
+/// characters, hyphens, underscores and periods.
+///
+/// The URL group methods call this before sending anything, so an invalid name fails fast
+/// instead of being mangled into the request path.
+pub fn validate_url_group_name(name: &str) -> Result<(), QstashError> {
+    if name.is_empty() {
+        return Err(QstashError::InvalidUrlGroupName(
+            "name must not be empty".to_string(),
+        ));
+    }
+    if let Some(invalid) = name
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+    {
+        return Err(QstashError::InvalidUrlGroupName(format!(
+            "{:?} contains the invalid character {:?}",
+            name, invalid
+        )));
+    }
+
+    Ok(())
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
 pub struct UrlGroup {
-    created_at: u64,
-    updated_at: u64,
-    name: String,
-    endpoints: Vec<Endpoint>,
+    /// The creation time of the URL group, in Unix milliseconds.
+    pub created_at: u64,
+    /// The last update time of the URL group, in Unix milliseconds.
+    pub updated_at: u64,
+    /// The name of the URL group.
+    pub name: String,
+    /// The endpoints a message published to the URL group is fanned out to.
+    pub endpoints: Vec<Endpoint>,
 }
 
-#[derive(Default, Serialize, Clone, Deserialize, Debug)]
+#[derive(Default, Serialize, Clone, Deserialize, Debug, PartialEq)]
 #[serde(default)]
 pub struct Endpoint {
+    /// Optional name of the endpoint, unique within its URL group.
     #[serde(skip_serializing_if = "String::is_empty")]
-    name: String,
+    pub name: String,
+    /// The URL messages are delivered to.
     #[serde(skip_serializing_if = "String::is_empty")]
-    url: String,
+    pub url: String,
 }
 
 #[cfg(test)]
@@ -546,4 +617,233 @@ mod tests {
             Err(QstashError::DailyRateLimitExceeded { reset: 1625097600 })
         ));
     }
+
+    #[test]
+    fn test_url_group_deserialization() {
+        let url_group: UrlGroup = serde_json::from_value(json!({
+            "createdAt": 1625097600000u64,
+            "updatedAt": 1625097700000u64,
+            "name": "test-group",
+            "endpoints": [
+                {"name": "endpoint1", "url": "https://example.com/1"},
+                {"url": "https://example.com/2"}
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(
+            url_group,
+            UrlGroup {
+                created_at: 1625097600000,
+                updated_at: 1625097700000,
+                name: "test-group".to_string(),
+                endpoints: vec![
+                    Endpoint {
+                        name: "endpoint1".to_string(),
+                        url: "https://example.com/1".to_string(),
+                    },
+                    Endpoint {
+                        name: String::new(),
+                        url: "https://example.com/2".to_string(),
+                    },
+                ],
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remove_url_group_cascade() {
+        let server = MockServer::start();
+        let list_schedules_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/schedules")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!([
+                    {"id": "schedule1", "cron": "0 0 * * *", "destination": "test-group"},
+                    {"id": "schedule2", "cron": "0 0 * * *", "destination": "https://example.com"},
+                    {"id": "schedule3", "cron": "0 1 * * *", "destination": "test-group"}
+                ]));
+        });
+        let remove_schedule1_mock = server.mock(|when, then| {
+            when.method(DELETE)
+                .path("/v2/schedules/schedule1")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16());
+        });
+        let remove_schedule3_mock = server.mock(|when, then| {
+            when.method(DELETE)
+                .path("/v2/schedules/schedule3")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16());
+        });
+        let remove_schedule2_mock = server.mock(|when, then| {
+            when.method(DELETE).path("/v2/schedules/schedule2");
+            then.status(StatusCode::OK.as_u16());
+        });
+        let remove_group_mock = server.mock(|when, then| {
+            when.method(DELETE)
+                .path("/v2/topics/test-group")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16());
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let removed = client.remove_url_group_cascade("test-group").await.unwrap();
+
+        assert_eq!(removed, vec!["schedule1", "schedule3"]);
+        list_schedules_mock.assert();
+        remove_schedule1_mock.assert();
+        remove_schedule3_mock.assert();
+        remove_schedule2_mock.assert_hits(0);
+        remove_group_mock.assert();
+    }
+
+    #[test]
+    fn test_validate_url_group_name() {
+        for name in ["group1", "my-group", "my_group", "v1.2", "A-Z_0.9"] {
+            assert!(
+                validate_url_group_name(name).is_ok(),
+                "{} should be valid",
+                name
+            );
+        }
+        for name in ["", "my/group", "my group", "group?x=1", "grüppe"] {
+            assert!(
+                matches!(
+                    validate_url_group_name(name),
+                    Err(QstashError::InvalidUrlGroupName(_))
+                ),
+                "{:?} should be invalid",
+                name
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_remove_url_group_rejects_invalid_name() {
+        let server = MockServer::start();
+        let remove_mock = server.mock(|when, then| {
+            when.method(DELETE);
+            then.status(StatusCode::OK.as_u16());
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let result = client.remove_url_group("groups/other").await;
+
+        remove_mock.assert_hits(0);
+        assert!(matches!(result, Err(QstashError::InvalidUrlGroupName(_))));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_url_group() {
+        let server = MockServer::start();
+        let get_url_group_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/topics/test-group")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!({
+                    "createdAt": 1625097600,
+                    "updatedAt": 1625097700,
+                    "name": "test-group",
+                    "endpoints": [
+                        {"name": "endpoint1", "url": "https://example.com/1"},
+                        {"url": "https://example.com/2"}
+                    ]
+                }));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let urls = client.resolve_url_group("test-group").await.unwrap();
+
+        get_url_group_mock.assert();
+        assert_eq!(urls, vec!["https://example.com/1", "https://example.com/2"]);
+    }
+
+    #[tokio::test]
+    async fn test_get_url_group_endpoints_success() {
+        let server = MockServer::start();
+        let get_url_group_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/topics/test-group")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .json_body(json!({
+                    "name": "test-group",
+                    "endpoints": [
+                        {"name": "endpoint1", "url": "https://example.com/1"},
+                        {"name": "endpoint2", "url": "https://example.com/2"}
+                    ]
+                }));
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let endpoints = client.get_url_group_endpoints("test-group").await.unwrap();
+
+        get_url_group_mock.assert();
+        assert_eq!(
+            endpoints,
+            vec![
+                Endpoint {
+                    name: "endpoint1".to_string(),
+                    url: "https://example.com/1".to_string(),
+                },
+                Endpoint {
+                    name: "endpoint2".to_string(),
+                    url: "https://example.com/2".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_url_group_endpoints_invalid_response() {
+        let server = MockServer::start();
+        let get_url_group_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/v2/topics/test-group")
+                .header("Authorization", "Bearer test_api_key");
+            then.status(StatusCode::OK.as_u16())
+                .header("Content-Type", "application/json")
+                .body("Invalid JSON");
+        });
+        let client = QstashClient::builder()
+            .base_url(Url::parse(&server.base_url()).unwrap())
+            .unwrap()
+            .api_key("test_api_key")
+            .build()
+            .expect("Failed to build QstashClient");
+
+        let result = client.get_url_group_endpoints("test-group").await;
+
+        get_url_group_mock.assert();
+        assert!(matches!(
+            result,
+            Err(QstashError::ResponseBodyParseError(_))
+        ));
+    }
 }